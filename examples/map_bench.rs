@@ -0,0 +1,138 @@
+// NOTE: headless frame time benchmark of the map, compares chunked and unchunked tiles
+// usage: cargo run --example map_bench -- [chunked|unchunked] [radius...]
+use std::{
+    env,
+    time::{Duration, Instant},
+};
+
+use bevy::{prelude::*, scene::SceneSpawner};
+use bevy_rapier3d::prelude::*;
+
+// NOTE: the example only drives the map plugin, most of the map api stays unused here
+#[allow(dead_code)]
+#[path = "../src/map/mod.rs"]
+mod map;
+
+use map::{MapDescriptor, MapPlugin, MapState};
+
+mod key_bindings {
+    use bevy::prelude::*;
+
+    #[derive(Resource)]
+    pub struct LocalPlayers {
+        pub num: usize,
+    }
+}
+
+const BALLS: usize = 20;
+const WARMUP_FRAMES: usize = 60;
+const FRAMES: usize = 300;
+
+struct BenchResult {
+    mean: Duration,
+    median: Duration,
+    entities: u32,
+}
+
+fn bench_app(radius: u32, chunked: bool) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugins(AssetPlugin::default())
+        .add_plugins((TransformPlugin, HierarchyPlugin))
+        .init_asset::<Mesh>()
+        .init_asset::<Image>()
+        .init_asset::<StandardMaterial>()
+        .init_resource::<SceneSpawner>()
+        .insert_resource(key_bindings::LocalPlayers { num: 2 })
+        .add_plugins(RapierPhysicsPlugin::<NoUserData>::default())
+        .insert_resource(RapierConfiguration {
+            timestep_mode: TimestepMode::Fixed {
+                dt: 1.0 / 60.0,
+                substeps: 1,
+            },
+            ..default()
+        })
+        .add_plugins(MapPlugin);
+    let mut des = app.world.resource_mut::<MapDescriptor>();
+    des.radius = radius;
+    des.chunked = chunked;
+    app
+}
+
+// NOTE: balls rolling over the island keep the physics busy like players would
+fn spawn_balls(app: &mut App) {
+    for i in 0..BALLS {
+        let angle = i as f32 * 0.7;
+        let dist = (i % 5) as f32 * 2.0;
+        app.world.spawn((
+            TransformBundle::from_transform(Transform::from_xyz(
+                angle.cos() * dist,
+                2.0,
+                angle.sin() * dist,
+            )),
+            RigidBody::Dynamic,
+            Collider::ball(0.5),
+            Velocity::linear(Vec3::new(angle.sin(), 0.0, angle.cos()) * 3.0),
+        ));
+    }
+}
+
+fn run(radius: u32, chunked: bool) -> BenchResult {
+    let mut app = bench_app(radius, chunked);
+    while *app.world.resource::<State<MapState>>().get() != MapState::GamePlay {
+        app.update();
+    }
+    spawn_balls(&mut app);
+    for _ in 0..WARMUP_FRAMES {
+        app.update();
+    }
+    let mut times: Vec<Duration> = (0..FRAMES)
+        .map(|_| {
+            let start = Instant::now();
+            app.update();
+            start.elapsed()
+        })
+        .collect();
+    times.sort();
+    BenchResult {
+        mean: times.iter().sum::<Duration>() / FRAMES as u32,
+        median: times[FRAMES / 2],
+        entities: app.world.entities().len(),
+    }
+}
+
+fn main() {
+    let mut modes = vec![true, false];
+    let mut radii = vec![];
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "chunked" => modes = vec![true],
+            "unchunked" => modes = vec![false],
+            _ => match arg.parse() {
+                Ok(radius) => radii.push(radius),
+                Err(_) => {
+                    eprintln!("usage: map_bench [chunked|unchunked] [radius...]");
+                    return;
+                }
+            },
+        }
+    }
+    if radii.is_empty() {
+        radii = vec![8, 16, 24, 32];
+    }
+
+    println!("radius  chunked  mean      median    entities");
+    for &radius in radii.iter() {
+        for &chunked in modes.iter() {
+            let result = run(radius, chunked);
+            println!(
+                "{:>6}  {:<7}  {:>5.2} ms  {:>5.2} ms  {:>8}",
+                radius,
+                if chunked { "yes" } else { "no" },
+                result.mean.as_secs_f64() * 1000.0,
+                result.median.as_secs_f64() * 1000.0,
+                result.entities
+            );
+        }
+    }
+}
@@ -0,0 +1,541 @@
+use std::time::Duration;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use rand::Rng;
+
+use crate::{
+    input::{ActionSample, InputRecording, InputReplay, InputSet, PlayerInput},
+    key_bindings::LocalPlayers,
+    map::{
+        navigation::{build_nav_graph, NavGraph},
+        GroundTile, MapDescriptor, MapState, MapTileHandle, WorldMap,
+    },
+    round::PlayerRespawned,
+    Player, PlayerActionState, PlayerActionValues, PlayerBundle, PlayerPhysicsBundle,
+};
+
+pub struct BotPlugin;
+
+impl Plugin for BotPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PracticeSettings>()
+            .add_systems(
+                OnEnter(MapState::GamePlay),
+                spawn_practice_box.after(build_nav_graph),
+            )
+            .add_systems(
+                Update,
+                (
+                    apply_practice_settings,
+                    reset_respawned_bots.after(apply_practice_settings),
+                    drive_bots.after(reset_respawned_bots),
+                    drive_dummies.after(apply_practice_settings),
+                )
+                    .in_set(InputSet),
+            );
+    }
+}
+
+// NOTE: the opponent next to the local players, bots press the same buttons a player would
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub enum PracticeBox {
+    DoNothing,
+    AlwaysShield,
+    AttackOnTimer,
+    JumpRepeatedly,
+    // NOTE: replays the recording in `PracticeSettings` in a loop
+    Replay,
+    Bot(BotDifficulty),
+}
+
+impl PracticeBox {
+    pub const DUMMIES: [PracticeBox; 5] = [
+        Self::DoNothing,
+        Self::AlwaysShield,
+        Self::AttackOnTimer,
+        Self::JumpRepeatedly,
+        Self::Replay,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::DoNothing => "Do nothing",
+            Self::AlwaysShield => "Always shield",
+            Self::AttackOnTimer => "Attack on timer",
+            Self::JumpRepeatedly => "Jump repeatedly",
+            Self::Replay => "Replay recording",
+            Self::Bot(difficulty) => difficulty.name(),
+        }
+    }
+
+    // NOTE: training dummies follow a fixed pattern, bots play the round
+    pub fn is_dummy(&self) -> bool {
+        !matches!(self, Self::Bot(_))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BotDifficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl BotDifficulty {
+    pub const ALL: [BotDifficulty; 3] = [Self::Easy, Self::Normal, Self::Hard];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Easy => "Easy",
+            Self::Normal => "Normal",
+            Self::Hard => "Hard",
+        }
+    }
+
+    fn skill(&self) -> BotSkill {
+        match self {
+            Self::Easy => BotSkill {
+                reaction: Duration::from_millis(600),
+                aim_error: 0.5,
+                shield_chance: 0.2,
+                charge_time: 0.6,
+                edge_margin: 0.5,
+                chase_range: 8.0,
+                careful: false,
+            },
+            Self::Normal => BotSkill {
+                reaction: Duration::from_millis(300),
+                aim_error: 0.25,
+                shield_chance: 0.5,
+                charge_time: 1.0,
+                edge_margin: 1.0,
+                chase_range: 12.0,
+                careful: true,
+            },
+            Self::Hard => BotSkill {
+                reaction: Duration::from_millis(120),
+                aim_error: 0.05,
+                shield_chance: 0.9,
+                charge_time: 1.6,
+                edge_margin: 1.5,
+                chase_range: 20.0,
+                careful: true,
+            },
+        }
+    }
+}
+
+// NOTE: ranges and margins are in tiles, `aim_error` is the largest miss in radians
+struct BotSkill {
+    reaction: Duration,
+    aim_error: f32,
+    shield_chance: f32,
+    // seconds the attack is held, longer charges hit harder
+    charge_time: f32,
+    edge_margin: f32,
+    chase_range: f32,
+    // only charges if the land behind the target catches the bot
+    careful: bool,
+}
+
+// NOTE: the opponent picked in the practice menu, used for new and existing practice boxes
+#[derive(Resource, Clone)]
+pub struct PracticeSettings {
+    pub opponent: PracticeBox,
+    pub attack_interval: Duration,
+    // how long the dummy charges each attack, see `PlayerActionValues::attack_strength`
+    pub attack_charge: Duration,
+    pub jump_interval: Duration,
+    pub recording: InputRecording,
+}
+
+impl Default for PracticeSettings {
+    fn default() -> Self {
+        Self {
+            opponent: PracticeBox::Bot(BotDifficulty::Normal),
+            attack_interval: Duration::from_millis(3000),
+            attack_charge: Duration::from_millis(1000),
+            jump_interval: Duration::from_millis(1500),
+            recording: InputRecording::default(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum BotGoal {
+    Wander,
+    Chase(Entity),
+    Charge(Entity),
+    Shield,
+    Retreat,
+}
+
+#[derive(Component)]
+pub struct BotBrain {
+    // NOTE: decisions are only made when the timer finishes, this is the reaction time
+    think: Timer,
+    goal: BotGoal,
+    aim: f32,
+    waypoints: Vec<Vec3>,
+    wander_target: Option<Vec3>,
+    // seconds since the practice box got its current behaviour, paces the dummies
+    elapsed: f32,
+}
+
+impl Default for BotBrain {
+    fn default() -> Self {
+        Self {
+            think: Timer::new(
+                BotDifficulty::default().skill().reaction,
+                TimerMode::Repeating,
+            ),
+            goal: BotGoal::Wander,
+            aim: 0.0,
+            waypoints: vec![],
+            wander_target: None,
+            elapsed: 0.0,
+        }
+    }
+}
+
+#[derive(SystemParam)]
+struct Arena<'w> {
+    graph: Res<'w, NavGraph>,
+    map: Res<'w, WorldMap>,
+    des: Res<'w, MapDescriptor>,
+}
+
+impl Arena<'_> {
+    // NOTE: the ground is crumbling or the coast is closer than `margin` tiles
+    fn in_danger(&self, pos: Vec3, ground: Option<MapTileHandle>, margin: f32) -> bool {
+        let crumbling = ground
+            .and_then(|handle| self.map.get(&handle))
+            .is_some_and(|tile| self.des.tile_warning(tile) > 0.0);
+        // on small islands every tile is close to the coast, only retreat if it helps
+        let tile_size = self.des.tile_size();
+        let safe = self
+            .safest_position(pos)
+            .and_then(|safe| self.graph.distance_to_edge(safe));
+        let near_edge = match (self.graph.distance_to_edge(pos), safe) {
+            (Some(dist), Some(safe)) => dist < margin * tile_size && dist + tile_size / 2.0 < safe,
+            _ => false,
+        };
+        crumbling || near_edge
+    }
+
+    // NOTE: walkable land all the way between the two positions
+    fn clear_line(&self, from: Vec3, to: Vec3) -> bool {
+        let tile_size = self.des.tile_size();
+        let steps = (from.xz().distance(to.xz()) / (tile_size / 2.0)).ceil() as usize;
+        (0..=steps).all(|i| {
+            let point = from.lerp(to, i as f32 / steps.max(1) as f32);
+            self.graph
+                .handle_at(point)
+                .and_then(|handle| self.graph.node(handle))
+                .is_some_and(|node| node.position.xz().distance(point.xz()) < 0.75 * tile_size)
+        })
+    }
+
+    fn waypoints(&self, from: Vec3, to: Vec3) -> Vec<Vec3> {
+        let mut path = self.graph.path(from, to).unwrap_or_default();
+        // the bot already stands on the first tile
+        if !path.is_empty() {
+            path.remove(0);
+        }
+        path
+    }
+
+    fn random_tile(&self) -> Option<Vec3> {
+        let inland: Vec<Vec3> = self
+            .graph
+            .nodes()
+            .filter(|(_, node)| node.edge_distance > 0)
+            .map(|(_, node)| node.position)
+            .collect();
+        let count = inland.len();
+        (count > 0).then(|| inland[rand::thread_rng().gen_range(0..count)])
+    }
+
+    fn safest_position(&self, near: Vec3) -> Option<Vec3> {
+        let handle = self.graph.safest_tile(near)?;
+        Some(self.graph.node(handle)?.position)
+    }
+}
+
+// NOTE: the practice box takes the spawn point after the local players
+fn spawn_practice_box(
+    mut cmd: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    local_players: Res<LocalPlayers>,
+    graph: Res<NavGraph>,
+    settings: Res<PracticeSettings>,
+) {
+    let spawn_points = graph.spawn_points(local_players.num + 1);
+    let ground = spawn_points.last().copied().unwrap_or_default();
+    cmd.spawn((
+        PbrBundle {
+            mesh: meshes.add(Cuboid::default()),
+            material: materials.add(Color::WHITE),
+            transform: Transform::from_translation(ground + Vec3::Y * 1.5),
+            ..default()
+        },
+        PlayerPhysicsBundle::default(),
+        PlayerBundle::default(),
+        settings.opponent,
+        BotBrain::default(),
+    ));
+}
+
+// NOTE: also sets up practice boxes spawned after the settings were changed
+fn apply_practice_settings(
+    mut cmd: Commands,
+    mut query: Query<(Entity, &mut PracticeBox, &mut BotBrain)>,
+    settings: Res<PracticeSettings>,
+) {
+    for (entity, mut practice_box, mut brain) in query.iter_mut() {
+        if !settings.is_changed() && !practice_box.is_added() {
+            continue;
+        }
+        *practice_box = settings.opponent;
+        *brain = BotBrain::default();
+        match settings.opponent {
+            PracticeBox::Replay => {
+                let replay = InputReplay::new(settings.recording.clone(), true);
+                cmd.entity(entity).insert(replay);
+            }
+            _ => {
+                cmd.entity(entity).remove::<InputReplay>();
+            }
+        }
+    }
+}
+
+// NOTE: goals and paths from before the fall are useless after a respawn
+fn reset_respawned_bots(
+    mut query: Query<&mut BotBrain>,
+    mut respawned_events: EventReader<PlayerRespawned>,
+) {
+    for respawned in respawned_events.read() {
+        if let Ok(mut brain) = query.get_mut(respawned.player) {
+            let elapsed = brain.elapsed;
+            *brain = BotBrain {
+                elapsed,
+                ..default()
+            };
+        }
+    }
+}
+
+fn drive_dummies(
+    mut query: Query<(
+        Entity,
+        &PracticeBox,
+        &mut BotBrain,
+        &mut PlayerInput,
+        &Transform,
+        &PlayerActionState,
+    )>,
+    players: Query<(Entity, &Transform), With<Player>>,
+    settings: Res<PracticeSettings>,
+    values: Res<PlayerActionValues>,
+    time: Res<Time>,
+) {
+    for (entity, practice_box, mut brain, mut input, trans, state) in query.iter_mut() {
+        brain.elapsed += time.delta_seconds();
+        let cycle = |interval: Duration| brain.elapsed % interval.as_secs_f32().max(0.1);
+        let mut sample = ActionSample::default();
+        match practice_box {
+            PracticeBox::DoNothing => {}
+            PracticeBox::AlwaysShield => {
+                // NOTE: the shield is raised again right away, but never held until it breaks
+                sample.shield = match state {
+                    PlayerActionState::Idle => !input.shield.pressed(),
+                    PlayerActionState::Shield(dur) => *dur < values.max_shield_duration * 3 / 4,
+                    _ => false,
+                };
+            }
+            PracticeBox::AttackOnTimer => {
+                sample.attack =
+                    cycle(settings.attack_interval) < settings.attack_charge.as_secs_f32();
+                // the attack is released towards the closest player
+                let nearest = players
+                    .iter()
+                    .filter(|(other, _)| *other != entity)
+                    .map(|(_, other)| other.translation - trans.translation)
+                    .min_by(|a, b| a.length().total_cmp(&b.length()));
+                if let Some(dir) = nearest.map(|dir| dir.normalize_or_zero()) {
+                    sample.move_axis = Vec2::new(dir.x, -dir.z);
+                }
+            }
+            PracticeBox::JumpRepeatedly => {
+                sample.jump = cycle(settings.jump_interval) < 0.1;
+            }
+            // the input is replayed by `InputReplay`
+            PracticeBox::Replay | PracticeBox::Bot(_) => continue,
+        }
+        input.apply(sample);
+    }
+}
+
+// NOTE: decide on a goal every reaction time, steer towards it every frame
+fn drive_bots(
+    mut bots: Query<(
+        Entity,
+        &PracticeBox,
+        &mut BotBrain,
+        &mut PlayerInput,
+        &Transform,
+        &PlayerActionState,
+        &GroundTile,
+    )>,
+    players: Query<(Entity, &Transform, &PlayerActionState), With<Player>>,
+    arena: Arena,
+    values: Res<PlayerActionValues>,
+    time: Res<Time>,
+) {
+    let tile_size = arena.des.tile_size();
+    for (entity, practice_box, mut brain, mut input, trans, state, ground) in bots.iter_mut() {
+        let PracticeBox::Bot(difficulty) = *practice_box else {
+            continue;
+        };
+        let skill = difficulty.skill();
+        let pos = trans.translation;
+        let nearest = players
+            .iter()
+            .filter(|(other, _, _)| *other != entity)
+            .min_by(|(_, a, _), (_, b, _)| {
+                let a = a.translation.distance(pos);
+                let b = b.translation.distance(pos);
+                a.total_cmp(&b)
+            });
+
+        brain.think.set_duration(skill.reaction);
+        brain.think.tick(time.delta());
+        if brain.think.just_finished() {
+            let mut rng = rand::thread_rng();
+            let threatened = players.iter().any(|(other, other_trans, other_state)| {
+                other != entity
+                    && matches!(other_state, PlayerActionState::PrepareAttack(_))
+                    && other_trans.translation.distance(pos) < 3.0 * tile_size
+            });
+            let goal = match (brain.goal, nearest) {
+                // NOTE: a started charge is finished, it is released in the steering below
+                (BotGoal::Charge(target), _)
+                    if matches!(state, PlayerActionState::PrepareAttack(_)) =>
+                {
+                    BotGoal::Charge(target)
+                }
+                _ if arena.in_danger(pos, ground.0, skill.edge_margin) => BotGoal::Retreat,
+                (BotGoal::Shield, _) if threatened => BotGoal::Shield,
+                _ if threatened && rng.gen::<f32>() < skill.shield_chance => BotGoal::Shield,
+                (_, Some((target, target_trans, _)))
+                    if target_trans.translation.distance(pos) < skill.chase_range * tile_size =>
+                {
+                    let target_pos = target_trans.translation;
+                    let dir = (target_pos - pos).normalize_or_zero();
+                    let landing = match skill.careful {
+                        true => target_pos + dir * 2.0 * tile_size,
+                        false => target_pos,
+                    };
+                    let aligned = target_pos.distance(pos) < 3.0 * tile_size
+                        && (target_pos.y - pos.y).abs() < 0.5
+                        && arena.clear_line(pos, landing);
+                    match aligned && matches!(state, PlayerActionState::Idle) {
+                        true => BotGoal::Charge(target),
+                        false => BotGoal::Chase(target),
+                    }
+                }
+                _ => BotGoal::Wander,
+            };
+
+            if goal != brain.goal {
+                brain.aim = rng.gen_range(-1.0..=1.0) * skill.aim_error;
+            }
+            let destination = match goal {
+                BotGoal::Retreat => arena.safest_position(pos),
+                BotGoal::Chase(target) => players
+                    .get(target)
+                    .ok()
+                    .map(|(_, target_trans, _)| target_trans.translation),
+                BotGoal::Wander => {
+                    let reached = brain
+                        .wander_target
+                        .is_some_and(|target| target.xz().distance(pos.xz()) < tile_size / 2.0);
+                    if brain.wander_target.is_none() || reached {
+                        brain.wander_target = arena.random_tile();
+                    }
+                    brain.wander_target
+                }
+                BotGoal::Charge(_) | BotGoal::Shield => None,
+            };
+            brain.waypoints = destination.map_or(vec![], |to| arena.waypoints(pos, to));
+            brain.goal = goal;
+        }
+
+        // waypoints count as reached once the bot rolls over them
+        let reached = brain
+            .waypoints
+            .first()
+            .is_some_and(|next| next.xz().distance(pos.xz()) < tile_size / 3.0);
+        if reached {
+            brain.waypoints.remove(0);
+        }
+
+        let mut sample = ActionSample::default();
+        let steer = |to: Vec3| {
+            let dir = (to - pos).normalize_or_zero();
+            Vec2::new(dir.x, -dir.z)
+        };
+        match brain.goal {
+            BotGoal::Charge(target) => {
+                let Ok((_, target_trans, _)) = players.get(target) else {
+                    brain.goal = BotGoal::Wander;
+                    input.apply(sample);
+                    continue;
+                };
+                let aim = Quat::from_rotation_y(brain.aim) * (target_trans.translation - pos);
+                sample.move_axis = steer(pos + aim);
+                sample.attack = match state {
+                    // the attack has to be pressed again to start a new charge
+                    PlayerActionState::Idle => !input.attack.pressed(),
+                    PlayerActionState::PrepareAttack(dur) => dur.as_secs_f32() < skill.charge_time,
+                    _ => {
+                        brain.goal = BotGoal::Chase(target);
+                        false
+                    }
+                };
+            }
+            BotGoal::Shield => {
+                // NOTE: let go before the shield breaks, a broken shield stuns the bot
+                let breaking = match state {
+                    PlayerActionState::Shield(dur) => *dur > values.max_shield_duration * 3 / 4,
+                    _ => false,
+                };
+                sample.shield = !breaking;
+                if let Some((_, attacker, _)) = nearest {
+                    // turning away from the attacker, so a blocked hit pushes less
+                    sample.move_axis = steer(pos - (attacker.translation - pos)) * 0.1;
+                }
+            }
+            BotGoal::Wander | BotGoal::Chase(_) | BotGoal::Retreat => {
+                if let Some(next) = brain.waypoints.first() {
+                    sample.move_axis = steer(*next);
+                    // steps up without a ramp are jumped
+                    let climb = next.y - (pos.y - 0.5);
+                    sample.jump = climb > 0.3 && ground.0.is_some() && !input.jump.pressed();
+                } else if let BotGoal::Chase(target) = brain.goal {
+                    if let Ok((_, target_trans, _)) = players.get(target) {
+                        sample.move_axis = steer(target_trans.translation);
+                    }
+                }
+                // wandering bots stroll, they only hurry when chasing or fleeing
+                if brain.goal == BotGoal::Wander {
+                    sample.move_axis *= 0.5;
+                }
+            }
+        }
+        input.apply(sample);
+    }
+}
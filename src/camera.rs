@@ -46,9 +46,18 @@ fn control_camera(
     descriptor: Res<CameraDescriptor>,
 ) {
     let mut cam = cam_query.single_mut();
-    let target = target_query.single();
+    // NOTE: follow the centre of all targets so every local player stays in view
+    let num_targets = target_query.iter().len();
+    if num_targets == 0 {
+        return;
+    }
+    let center = target_query
+        .iter()
+        .map(|trans| trans.translation)
+        .sum::<Vec3>()
+        / num_targets as f32;
 
-    let opt_cam_pos = target.translation + descriptor.opt_postition;
+    let opt_cam_pos = center + descriptor.opt_postition;
     let diff = opt_cam_pos - cam.translation;
     let dir = vec3(diff.x, 0.0, diff.z).normalize_or_zero();
     let amount = (vec3(diff.x, 0.0, diff.z).length() / descriptor.window).clamp(0.0, 1.0);
@@ -4,6 +4,8 @@ use bevy::{math::vec3, prelude::*};
 use bevy_rapier3d::prelude::*;
 use rand::Rng;
 
+use crate::map::MapDescriptor;
+
 #[derive(Component)]
 pub struct Coin;
 
@@ -44,6 +46,12 @@ impl Default for CoinSpawnerDescriptor {
     }
 }
 
+#[derive(Resource)]
+struct CoinAssets {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+}
+
 #[derive(Resource, Deref, DerefMut)]
 struct CoinTimer(Timer);
 
@@ -63,6 +71,7 @@ impl Plugin for CoinSpawnerPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(self.descriptor.clone())
             .insert_resource(CoinTimer::default())
+            .add_systems(Startup, setup_coin_assets)
             .add_systems(
                 Update,
                 (spawn_coins, update_rotated, tick_timer, despawn_coins),
@@ -70,14 +79,26 @@ impl Plugin for CoinSpawnerPlugin {
     }
 }
 
-fn spawn_coins(
+fn setup_coin_assets(
     mut cmd: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    cmd.insert_resource(CoinAssets {
+        mesh: meshes.add(Sphere::default()),
+        material: materials.add(Color::GOLD),
+    });
+}
+
+// NOTE: coins are only spent on raising tiles, so none spawn while raising is disabled
+fn spawn_coins(
+    mut cmd: Commands,
+    assets: Res<CoinAssets>,
     timer: Res<CoinTimer>,
     des: Res<CoinSpawnerDescriptor>,
+    map_des: Res<MapDescriptor>,
 ) {
-    if !timer.just_finished() {
+    if !timer.just_finished() || map_des.raise_cost.is_none() {
         return;
     }
     let radius = des.spawn_radius as i32;
@@ -85,15 +106,13 @@ fn spawn_coins(
     let x = rng.gen_range(-radius..=radius) as f32;
     let z = rng.gen_range(-radius..=radius) as f32;
 
-    let mesh = meshes.add(Sphere::default());
-    let material = materials.add(Color::GOLD);
     cmd.spawn(Coin)
         .insert(CoinPhysicsBundle::default())
         .insert(Rotated(1.0))
         .insert(Transform::from_xyz(x, des.spawn_height, z))
         .insert(PbrBundle {
-            mesh,
-            material,
+            mesh: assets.mesh.clone(),
+            material: assets.material.clone(),
             transform: Transform::from_xyz(x, des.spawn_height, z).with_scale(vec3(0.3, 0.5, 0.5)),
             ..default()
         });
@@ -1,9 +1,9 @@
 use bevy::prelude::*;
 
-use crate::{
-    map::{MapTileHandle, WorldMap},
-    Grounded, Player, PlayerActionState,
-};
+use crate::{map::navigation::NavGraph, Grounded, Player, PlayerActionState};
+
+#[derive(Resource, Default)]
+pub struct ShowNavGraph(bool);
 
 pub fn debug_material_color(
     query: Query<(&Handle<StandardMaterial>, &Grounded, &PlayerActionState), With<Player>>,
@@ -20,25 +20,55 @@ pub fn debug_material_color(
             (Grounded::Grounded, PlayerActionState::PrepareAttack(_)) => Color::GOLD,
             (Grounded::Grounded, PlayerActionState::Attack(_, _)) => Color::RED,
             (Grounded::Grounded, PlayerActionState::Shield(_)) => Color::BLUE,
+            (_, PlayerActionState::Stunned(_)) => Color::PURPLE,
+            (_, PlayerActionState::GroundPound) => Color::ORANGE_RED,
             (_, _) => Color::PINK,
         };
         material.base_color = color;
     }
 }
 
-pub fn debug_map_material_color(
-    query: Query<(&Handle<StandardMaterial>, &MapTileHandle)>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    map: Res<WorldMap>,
+pub fn toggle_nav_graph(keys: Res<ButtonInput<KeyCode>>, mut show: ResMut<ShowNavGraph>) {
+    if keys.just_pressed(KeyCode::F3) {
+        show.0 = !show.0;
+    }
+}
+
+// NOTE: coast tiles are red, every player gets a path to the safest tile and a circle reaching the coast
+pub fn debug_draw_nav_graph(
+    mut gizmos: Gizmos,
+    show: Res<ShowNavGraph>,
+    graph: Res<NavGraph>,
+    players: Query<&Transform, With<Player>>,
 ) {
-    for (mat_handle, map_handle) in query.iter() {
-        let Some(material) = &mut materials.get_mut(mat_handle) else {
-            continue;
-        };
-        let Some(tile) = map.get(map_handle) else {
-            continue;
+    if !show.0 {
+        return;
+    }
+    let lift = Vec3::Y * 0.05;
+    for (_, node) in graph.nodes() {
+        let color = match node.edge_distance {
+            0 => Color::RED,
+            _ => Color::WHITE,
         };
-        let color = Color::rgb(0.0, tile.constitution, 0.0);
-        material.base_color = color;
+        gizmos.sphere(node.position + lift, Quat::IDENTITY, 0.1, color);
+        for (n, _) in node.links() {
+            let Some(other) = graph.node(*n) else {
+                continue;
+            };
+            gizmos.line(node.position + lift, other.position + lift, Color::GRAY);
+        }
+    }
+    for trans in players.iter() {
+        let pos = trans.translation;
+        if let Some(path) = graph
+            .safest_tile(pos)
+            .and_then(|safest| graph.node(safest))
+            .and_then(|safest| graph.path(pos, safest.position))
+        {
+            gizmos.linestrip(path.into_iter().map(|p| p + lift * 2.0), Color::GREEN);
+        }
+        if let Some(dist) = graph.distance_to_edge(pos) {
+            gizmos.circle(pos, Direction3d::Y, dist, Color::YELLOW);
+        }
     }
 }
@@ -0,0 +1,204 @@
+use bevy::prelude::*;
+
+use crate::{
+    key_bindings::{assign_gamepads, Action, InputDevice, InputReader},
+    ui::rebind_menu::RebindMenu,
+};
+
+pub struct InputPlugin;
+
+// NOTE: all systems producing `PlayerInput` run in this set, gameplay runs after it
+#[derive(SystemSet, Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct InputSet;
+
+impl Plugin for InputPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PreUpdate, assign_gamepads).add_systems(
+            Update,
+            (
+                read_device_input,
+                replay_input,
+                record_input.after(read_device_input).after(replay_input),
+            )
+                .in_set(InputSet),
+        );
+    }
+}
+
+#[derive(Clone, Copy, Default, Debug)]
+pub struct ButtonState {
+    pressed: bool,
+    just_pressed: bool,
+    just_released: bool,
+}
+
+impl ButtonState {
+    fn update(&mut self, pressed: bool) {
+        self.just_pressed = pressed && !self.pressed;
+        self.just_released = !pressed && self.pressed;
+        self.pressed = pressed;
+    }
+
+    pub fn pressed(&self) -> bool {
+        self.pressed
+    }
+
+    pub fn just_pressed(&self) -> bool {
+        self.just_pressed
+    }
+
+    pub fn just_released(&self) -> bool {
+        self.just_released
+    }
+}
+
+// NOTE: raw input of a single frame, as produced by a device, a bot or a replay
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub struct ActionSample {
+    pub move_axis: Vec2,
+    pub jump: bool,
+    pub attack: bool,
+    pub shield: bool,
+    pub raise: bool,
+}
+
+#[derive(Component, Clone, Default, Debug)]
+pub struct PlayerInput {
+    pub move_axis: Vec2,
+    pub jump: ButtonState,
+    pub attack: ButtonState,
+    pub shield: ButtonState,
+    pub raise: ButtonState,
+}
+
+impl PlayerInput {
+    pub fn apply(&mut self, sample: ActionSample) {
+        self.move_axis = sample.move_axis.clamp_length_max(1.0);
+        self.jump.update(sample.jump);
+        self.attack.update(sample.attack);
+        self.shield.update(sample.shield);
+        self.raise.update(sample.raise);
+    }
+
+    pub fn sample(&self) -> ActionSample {
+        ActionSample {
+            move_axis: self.move_axis,
+            jump: self.jump.pressed(),
+            attack: self.attack.pressed(),
+            shield: self.shield.pressed(),
+            raise: self.raise.pressed(),
+        }
+    }
+
+    // NOTE: the move axis in world space, x is right and y is forward
+    pub fn move_direction(&self) -> Vec3 {
+        Vec3::new(self.move_axis.x, 0.0, -self.move_axis.y)
+    }
+}
+
+#[derive(Clone, Default, Debug)]
+pub struct InputRecording {
+    samples: Vec<(f32, ActionSample)>,
+}
+
+impl InputRecording {
+    pub fn duration(&self) -> f32 {
+        self.samples.last().map_or(0.0, |(time, _)| *time)
+    }
+}
+
+#[derive(Component, Default)]
+pub struct InputRecorder {
+    pub recording: InputRecording,
+    elapsed: f32,
+}
+
+#[derive(Component)]
+pub struct InputReplay {
+    recording: InputRecording,
+    elapsed: f32,
+    cursor: usize,
+    looping: bool,
+}
+
+impl InputReplay {
+    pub fn new(recording: InputRecording, looping: bool) -> Self {
+        Self {
+            recording,
+            elapsed: 0.0,
+            cursor: 0,
+            looping,
+        }
+    }
+}
+
+fn read_device_input(
+    mut query: Query<(&InputDevice, &mut PlayerInput)>,
+    input: InputReader,
+    menu: Option<Res<RebindMenu>>,
+) {
+    // NOTE: players let go of everything while the key bindings are being edited
+    let menu_open = menu.is_some_and(|menu| menu.open);
+    for (device, mut player_input) in query.iter_mut() {
+        if menu_open {
+            player_input.apply(ActionSample::default());
+            continue;
+        }
+        let direction = input.move_direction(device);
+        player_input.apply(ActionSample {
+            move_axis: Vec2::new(direction.x, -direction.z),
+            jump: input.pressed(device, Action::Jump),
+            attack: input.pressed(device, Action::Attack),
+            shield: input.pressed(device, Action::Shield),
+            raise: input.pressed(device, Action::Raise),
+        });
+    }
+}
+
+fn record_input(mut query: Query<(&mut InputRecorder, &PlayerInput)>, time: Res<Time>) {
+    for (mut recorder, player_input) in query.iter_mut() {
+        recorder.elapsed += time.delta_seconds();
+        let sample = (recorder.elapsed, player_input.sample());
+        recorder.recording.samples.push(sample);
+    }
+}
+
+// NOTE: buttons pressed at any point during the frame stay pressed so short taps are not lost
+fn replay_input(mut query: Query<(&mut InputReplay, &mut PlayerInput)>, time: Res<Time>) {
+    for (mut replay, mut player_input) in query.iter_mut() {
+        if replay.recording.samples.is_empty() {
+            player_input.apply(ActionSample::default());
+            continue;
+        }
+        replay.elapsed += time.delta_seconds();
+        if replay.looping && replay.elapsed > replay.recording.duration() {
+            replay.elapsed -= replay.recording.duration();
+            replay.cursor = 0;
+        }
+
+        let mut sample: Option<ActionSample> = None;
+        while let Some(&(at, next)) = replay.recording.samples.get(replay.cursor) {
+            if at > replay.elapsed {
+                break;
+            }
+            replay.cursor += 1;
+            sample = Some(match sample {
+                Some(prev) => ActionSample {
+                    move_axis: next.move_axis,
+                    jump: prev.jump || next.jump,
+                    attack: prev.attack || next.attack,
+                    shield: prev.shield || next.shield,
+                    raise: prev.raise || next.raise,
+                },
+                None => next,
+            });
+        }
+        let finished = replay.cursor >= replay.recording.samples.len() && !replay.looping;
+        let sample = match sample {
+            Some(sample) => sample,
+            None if finished => ActionSample::default(),
+            None => player_input.sample(),
+        };
+        player_input.apply(sample);
+    }
+}
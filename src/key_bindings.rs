@@ -1,26 +1,474 @@
-use bevy::{ecs::system::Resource, input::keyboard::KeyCode};
+use std::{fs, path::PathBuf};
+
+use bevy::{
+    ecs::system::SystemParam,
+    input::gamepad::{
+        Gamepad, GamepadAxis, GamepadAxisType, GamepadButton, GamepadButtonType,
+        GamepadConnectionEvent,
+    },
+    prelude::*,
+    utils::HashMap,
+};
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::PlayerSlot;
+
+pub const MAX_LOCAL_PLAYERS: usize = 4;
 
 #[derive(Resource)]
+pub struct LocalPlayers {
+    pub num: usize,
+}
+
+impl Default for LocalPlayers {
+    fn default() -> Self {
+        Self { num: 2 }
+    }
+}
+
+// NOTE: gamepads assigned to local player slots, kept across rounds
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct GamepadAssignments(HashMap<usize, Gamepad>);
+
+#[derive(Component, Clone)]
+pub enum InputDevice {
+    Keyboard(KeyBindings),
+    Gamepad(Gamepad, GamepadBindings),
+}
+
+impl InputDevice {
+    pub fn for_slot(
+        slot: usize,
+        assignments: &GamepadAssignments,
+        config: &KeyBindingsConfig,
+    ) -> Self {
+        match assignments.get(&slot) {
+            Some(&gamepad) => Self::Gamepad(gamepad, GamepadBindings::default()),
+            None => Self::Keyboard(config.slot(slot)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Action {
+    MoveForward,
+    MoveBackward,
+    MoveLeft,
+    MoveRight,
+    Jump,
+    Attack,
+    Shield,
+    Raise,
+}
+
+impl Action {
+    pub const ALL: [Action; 8] = [
+        Action::MoveForward,
+        Action::MoveBackward,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Jump,
+        Action::Attack,
+        Action::Shield,
+        Action::Raise,
+    ];
+}
+
+// NOTE: key bindings of all local player slots, persisted in the user's config directory
+#[derive(Resource, Clone, Serialize, Deserialize)]
+#[serde(from = "StoredKeyBindingsConfig")]
+pub struct KeyBindingsConfig {
+    pub players: Vec<KeyBindings>,
+}
+
+// NOTE: configs saved before an action existed lack its field, they get the default keys of the
+// slot, an action unbound on purpose is stored as an empty list and stays unbound
+#[derive(Deserialize)]
+struct StoredKeyBindingsConfig {
+    players: Vec<StoredKeyBindings>,
+}
+
+#[derive(Deserialize)]
+struct StoredKeyBindings {
+    move_forward: Vec<KeyCode>,
+    move_backward: Vec<KeyCode>,
+    move_left: Vec<KeyCode>,
+    move_right: Vec<KeyCode>,
+    move_jump: Vec<KeyCode>,
+    attack: Vec<KeyCode>,
+    shield: Vec<KeyCode>,
+    #[serde(default, deserialize_with = "present_keys")]
+    raise: Option<Vec<KeyCode>>,
+}
+
+// the field is stored as a plain list, `None` is only left by the default of a missing field
+fn present_keys<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Vec<KeyCode>>, D::Error> {
+    Vec::deserialize(deserializer).map(Some)
+}
+
+impl From<StoredKeyBindingsConfig> for KeyBindingsConfig {
+    fn from(stored: StoredKeyBindingsConfig) -> Self {
+        let players = stored
+            .players
+            .into_iter()
+            .enumerate()
+            .map(|(slot, bindings)| KeyBindings {
+                move_forward: bindings.move_forward,
+                move_backward: bindings.move_backward,
+                move_left: bindings.move_left,
+                move_right: bindings.move_right,
+                move_jump: bindings.move_jump,
+                attack: bindings.attack,
+                shield: bindings.shield,
+                raise: bindings
+                    .raise
+                    .unwrap_or_else(|| KeyBindings::for_slot(slot).raise),
+            })
+            .collect();
+        Self { players }
+    }
+}
+
+impl Default for KeyBindingsConfig {
+    fn default() -> Self {
+        Self {
+            players: (0..MAX_LOCAL_PLAYERS).map(KeyBindings::for_slot).collect(),
+        }
+    }
+}
+
+impl KeyBindingsConfig {
+    fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("bouncy-island").join("key_bindings.ron"))
+    }
+
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+        let Ok(content) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        match ron::from_str::<Self>(&content) {
+            Ok(config) => config,
+            Err(err) => {
+                error!("Unable to parse key bindings {:?}: {}", path, err);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        let Some(path) = Self::path() else {
+            error!("Unable to find a config directory for the key bindings!");
+            return;
+        };
+        let content = match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+            Ok(content) => content,
+            Err(err) => {
+                error!("Unable to serialize key bindings: {}", err);
+                return;
+            }
+        };
+        if let Some(dir) = path.parent() {
+            if let Err(err) = fs::create_dir_all(dir) {
+                error!("Unable to create config directory {:?}: {}", dir, err);
+                return;
+            }
+        }
+        if let Err(err) = fs::write(&path, content) {
+            error!("Unable to save key bindings to {:?}: {}", path, err);
+        }
+    }
+
+    pub fn slot(&self, slot: usize) -> KeyBindings {
+        self.players
+            .get(slot)
+            .cloned()
+            .unwrap_or_else(|| KeyBindings::for_slot(slot))
+    }
+
+    pub fn slot_mut(&mut self, slot: usize) -> &mut KeyBindings {
+        while self.players.len() <= slot {
+            let next = self.players.len();
+            self.players.push(KeyBindings::for_slot(next));
+        }
+        &mut self.players[slot]
+    }
+
+    // NOTE: all other slot/action pairs the key is bound to
+    pub fn conflicts(&self, slot: usize, action: Action, key: KeyCode) -> Vec<(usize, Action)> {
+        let mut conflicts = Vec::new();
+        for (other_slot, bindings) in self.players.iter().enumerate() {
+            for other_action in Action::ALL {
+                if other_slot == slot && other_action == action {
+                    continue;
+                }
+                if bindings.keys(other_action).contains(&key) {
+                    conflicts.push((other_slot, other_action));
+                }
+            }
+        }
+        conflicts
+    }
+}
+
+#[derive(Clone, Serialize)]
 pub struct KeyBindings {
-    pub move_forward: KeyCode,
-    pub move_backward: KeyCode,
-    pub move_left: KeyCode,
-    pub move_right: KeyCode,
-    pub move_jump: KeyCode,
-    pub attack: KeyCode,
-    pub shield: KeyCode,
+    pub move_forward: Vec<KeyCode>,
+    pub move_backward: Vec<KeyCode>,
+    pub move_left: Vec<KeyCode>,
+    pub move_right: Vec<KeyCode>,
+    pub move_jump: Vec<KeyCode>,
+    pub attack: Vec<KeyCode>,
+    pub shield: Vec<KeyCode>,
+    pub raise: Vec<KeyCode>,
 }
 
 impl Default for KeyBindings {
     fn default() -> Self {
         Self {
-            move_forward: KeyCode::KeyW,
-            move_backward: KeyCode::KeyS,
-            move_left: KeyCode::KeyA,
-            move_right: KeyCode::KeyD,
-            move_jump: KeyCode::Space,
-            attack: KeyCode::KeyH,
-            shield: KeyCode::KeyJ,
+            move_forward: vec![KeyCode::KeyW],
+            move_backward: vec![KeyCode::KeyS],
+            move_left: vec![KeyCode::KeyA],
+            move_right: vec![KeyCode::KeyD],
+            move_jump: vec![KeyCode::Space],
+            attack: vec![KeyCode::KeyH],
+            shield: vec![KeyCode::KeyJ],
+            raise: vec![KeyCode::KeyG],
+        }
+    }
+}
+
+impl KeyBindings {
+    pub fn arrows() -> Self {
+        Self {
+            move_forward: vec![KeyCode::ArrowUp],
+            move_backward: vec![KeyCode::ArrowDown],
+            move_left: vec![KeyCode::ArrowLeft],
+            move_right: vec![KeyCode::ArrowRight],
+            move_jump: vec![KeyCode::Numpad0],
+            attack: vec![KeyCode::Numpad1],
+            shield: vec![KeyCode::Numpad2],
+            raise: vec![KeyCode::Numpad3],
+        }
+    }
+
+    pub fn okl() -> Self {
+        Self {
+            move_forward: vec![KeyCode::KeyO],
+            move_backward: vec![KeyCode::KeyL],
+            move_left: vec![KeyCode::KeyK],
+            move_right: vec![KeyCode::Semicolon],
+            move_jump: vec![KeyCode::Slash],
+            attack: vec![KeyCode::Period],
+            shield: vec![KeyCode::Comma],
+            raise: vec![KeyCode::KeyM],
+        }
+    }
+
+    pub fn numpad() -> Self {
+        Self {
+            move_forward: vec![KeyCode::Numpad8],
+            move_backward: vec![KeyCode::Numpad5],
+            move_left: vec![KeyCode::Numpad4],
+            move_right: vec![KeyCode::Numpad6],
+            move_jump: vec![KeyCode::NumpadEnter],
+            attack: vec![KeyCode::NumpadAdd],
+            shield: vec![KeyCode::NumpadSubtract],
+            raise: vec![KeyCode::NumpadMultiply],
+        }
+    }
+
+    // NOTE: default binding set of the local player in the given slot
+    pub fn for_slot(slot: usize) -> Self {
+        match slot % 4 {
+            0 => Self::default(),
+            1 => Self::arrows(),
+            2 => Self::okl(),
+            _ => Self::numpad(),
+        }
+    }
+
+    pub fn keys(&self, action: Action) -> &Vec<KeyCode> {
+        match action {
+            Action::MoveForward => &self.move_forward,
+            Action::MoveBackward => &self.move_backward,
+            Action::MoveLeft => &self.move_left,
+            Action::MoveRight => &self.move_right,
+            Action::Jump => &self.move_jump,
+            Action::Attack => &self.attack,
+            Action::Shield => &self.shield,
+            Action::Raise => &self.raise,
+        }
+    }
+
+    pub fn keys_mut(&mut self, action: Action) -> &mut Vec<KeyCode> {
+        match action {
+            Action::MoveForward => &mut self.move_forward,
+            Action::MoveBackward => &mut self.move_backward,
+            Action::MoveLeft => &mut self.move_left,
+            Action::MoveRight => &mut self.move_right,
+            Action::Jump => &mut self.move_jump,
+            Action::Attack => &mut self.attack,
+            Action::Shield => &mut self.shield,
+            Action::Raise => &mut self.raise,
+        }
+    }
+
+    pub fn pressed(&self, keys: &ButtonInput<KeyCode>, action: Action) -> bool {
+        keys.any_pressed(self.keys(action).iter().copied())
+    }
+
+    pub fn move_direction(&self, keys: &ButtonInput<KeyCode>) -> Vec3 {
+        let forward = -Vec3::Z;
+        let right = Vec3::X;
+        let mut direction = Vec3::ZERO;
+        if self.pressed(keys, Action::MoveForward) {
+            direction += forward;
+        }
+        if self.pressed(keys, Action::MoveBackward) {
+            direction -= forward;
+        }
+        if self.pressed(keys, Action::MoveRight) {
+            direction += right;
+        }
+        if self.pressed(keys, Action::MoveLeft) {
+            direction -= right;
+        }
+        direction.normalize_or_zero()
+    }
+}
+
+#[derive(Clone)]
+pub struct GamepadBindings {
+    pub move_x: GamepadAxisType,
+    pub move_y: GamepadAxisType,
+    pub deadzone: f32,
+    pub move_jump: GamepadButtonType,
+    pub attack: GamepadButtonType,
+    pub shield: GamepadButtonType,
+    pub raise: GamepadButtonType,
+}
+
+impl Default for GamepadBindings {
+    fn default() -> Self {
+        Self {
+            move_x: GamepadAxisType::LeftStickX,
+            move_y: GamepadAxisType::LeftStickY,
+            deadzone: 0.15,
+            move_jump: GamepadButtonType::South,
+            attack: GamepadButtonType::West,
+            shield: GamepadButtonType::RightTrigger,
+            raise: GamepadButtonType::North,
+        }
+    }
+}
+
+impl GamepadBindings {
+    // NOTE: movement is read from the stick, the other actions map to buttons
+    pub fn button(&self, action: Action) -> Option<GamepadButtonType> {
+        match action {
+            Action::Jump => Some(self.move_jump),
+            Action::Attack => Some(self.attack),
+            Action::Shield => Some(self.shield),
+            Action::Raise => Some(self.raise),
+            _ => None,
+        }
+    }
+
+    // NOTE: stick direction rescaled so that its length grows from 0 at the deadzone to 1
+    pub fn move_direction(&self, gamepad: Gamepad, axes: &Axis<GamepadAxis>) -> Vec3 {
+        let x = axes
+            .get(GamepadAxis::new(gamepad, self.move_x))
+            .unwrap_or(0.0);
+        let y = axes
+            .get(GamepadAxis::new(gamepad, self.move_y))
+            .unwrap_or(0.0);
+        let stick = Vec2::new(x, y);
+        let length = stick.length();
+        if length < self.deadzone {
+            return Vec3::ZERO;
+        }
+        let amount = ((length - self.deadzone) / (1.0 - self.deadzone)).min(1.0);
+        let direction = stick / length * amount;
+        Vec3::new(direction.x, 0.0, -direction.y)
+    }
+}
+
+// NOTE: reads player input from whatever device is bound to the player
+#[derive(SystemParam)]
+pub struct InputReader<'w> {
+    keys: Res<'w, ButtonInput<KeyCode>>,
+    buttons: Res<'w, ButtonInput<GamepadButton>>,
+    axes: Res<'w, Axis<GamepadAxis>>,
+}
+
+impl<'w> InputReader<'w> {
+    pub fn move_direction(&self, device: &InputDevice) -> Vec3 {
+        match device {
+            InputDevice::Keyboard(bindings) => bindings.move_direction(&self.keys),
+            InputDevice::Gamepad(gamepad, bindings) => {
+                bindings.move_direction(*gamepad, &self.axes)
+            }
+        }
+    }
+
+    pub fn pressed(&self, device: &InputDevice, action: Action) -> bool {
+        match device {
+            InputDevice::Keyboard(bindings) => bindings.pressed(&self.keys, action),
+            InputDevice::Gamepad(gamepad, bindings) => bindings
+                .button(action)
+                .is_some_and(|button| self.buttons.pressed(GamepadButton::new(*gamepad, button))),
+        }
+    }
+}
+
+// NOTE: hands newly connected gamepads to the first free player slot
+pub fn assign_gamepads(
+    mut connection_events: EventReader<GamepadConnectionEvent>,
+    mut assignments: ResMut<GamepadAssignments>,
+    mut local_players: ResMut<LocalPlayers>,
+    mut query: Query<(&PlayerSlot, &mut InputDevice)>,
+    config: Res<KeyBindingsConfig>,
+) {
+    for event in connection_events.read() {
+        let gamepad = event.gamepad;
+        if event.connected() {
+            if assignments.values().any(|&assigned| assigned == gamepad) {
+                continue;
+            }
+            let Some(slot) = (0..MAX_LOCAL_PLAYERS).find(|slot| !assignments.contains_key(slot))
+            else {
+                warn!("No free player slot for {:?}!", gamepad);
+                continue;
+            };
+            info!("Assigned {:?} to player slot {}.", gamepad, slot);
+            assignments.insert(slot, gamepad);
+            // NOTE: a player joining on a new slot takes part from the next round on
+            local_players.num = local_players.num.max(slot + 1);
+            for (player_slot, mut device) in query.iter_mut() {
+                if player_slot.0 == slot {
+                    *device = InputDevice::Gamepad(gamepad, GamepadBindings::default());
+                }
+            }
+        } else {
+            let Some(slot) = assignments
+                .iter()
+                .find_map(|(&slot, &assigned)| (assigned == gamepad).then_some(slot))
+            else {
+                continue;
+            };
+            info!("{:?} of player slot {} disconnected.", gamepad, slot);
+            assignments.remove(&slot);
+            for (player_slot, mut device) in query.iter_mut() {
+                if player_slot.0 == slot {
+                    *device = InputDevice::Keyboard(config.slot(slot));
+                }
+            }
         }
     }
 }
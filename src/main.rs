@@ -1,13 +1,16 @@
 use std::f32::consts::PI;
 use std::time::Duration;
 
+mod bot;
 mod camera;
 mod coin;
 mod coin_collector;
 mod debug_utils;
+mod input;
 mod key_bindings;
 mod map;
 mod player;
+mod round;
 mod ui;
 mod water;
 
@@ -20,13 +23,26 @@ use bevy_rapier3d::prelude::*;
 use camera::CameraTarget;
 use coin_collector::{collect_coins, debug_log_coin_collection, CoinCollection};
 use debug_utils::*;
-use key_bindings::KeyBindings;
+use input::{InputSet, PlayerInput};
+use key_bindings::{GamepadAssignments, InputDevice, KeyBindingsConfig, LocalPlayers};
+use map::{
+    chunks::TileLookup,
+    impact::TileImpact,
+    navigation::{build_nav_graph, NavGraph},
+    regrowth::RaiseTile,
+    GroundTile, MapDescriptor, WorldMap,
+};
 use player::player::*;
+use round::Invulnerable;
 
 fn main() {
     App::new()
-        .init_resource::<KeyBindings>()
+        .init_resource::<LocalPlayers>()
+        .init_resource::<GamepadAssignments>()
+        .insert_resource(KeyBindingsConfig::load())
         .init_resource::<PlayerActionValues>()
+        .add_event::<PlayerLanded>()
+        .add_event::<PlayerHit>()
         .insert_resource(Msaa::Off)
         .add_plugins(DefaultPlugins)
         .add_plugins(WorldInspectorPlugin::new())
@@ -35,7 +51,15 @@ fn main() {
             camera::CameraPlugin::default(),
             map::MapPlugin,
             water::WaterPlugin::default(),
-            // coin::CoinSpawnerPlugin::default(),
+            round::RoundPlugin::default(),
+            input::InputPlugin,
+            ui::rebind_menu::RebindMenuPlugin,
+            ui::map_menu::MapMenuPlugin,
+            ui::practice_menu::PracticeMenuPlugin,
+            ui::training_hud::TrainingHudPlugin,
+            ui::PlayerUIPlugin,
+            bot::BotPlugin,
+            coin::CoinSpawnerPlugin::default(),
         ))
         .insert_resource(RapierConfiguration {
             gravity: Vec3::new(0.0, -19.62, 0.0),
@@ -49,34 +73,41 @@ fn main() {
         // .add_plugins(RapierDebugRenderPlugin::default())
         .add_plugins(FrameTimeDiagnosticsPlugin::default())
         .add_plugins(LogDiagnosticsPlugin::default())
+        .init_resource::<ShowNavGraph>()
         .add_systems(Startup, setup)
+        .add_systems(
+            OnEnter(map::MapState::GamePlay),
+            spawn_players.after(build_nav_graph),
+        )
         .add_systems(
             Update,
             (
                 player_move,
                 check_player_grounded,
+                apply_ground_effects.after(player_move),
                 player_action_system,
                 player_collision_system,
-            ),
+                ground_impact_system
+                    .after(check_player_grounded)
+                    .after(player_action_system),
+                raise_tile_system.after(check_player_grounded),
+            )
+                .after(InputSet),
         )
         .add_systems(Update, collect_coins)
-        .add_systems(Update, check_game_over)
         .add_systems(
             Update,
             (
                 debug_material_color,
-                // debug_map_material_color,
+                toggle_nav_graph,
+                debug_draw_nav_graph.after(toggle_nav_graph),
                 // debug_log_coin_collection,
             ),
         )
         .run();
 }
 
-fn setup(
-    mut cmd: Commands,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    mut meshes: ResMut<Assets<Mesh>>,
-) {
+fn setup(mut cmd: Commands) {
     cmd.spawn(DirectionalLightBundle {
         directional_light: DirectionalLight {
             illuminance: 1_000.0,
@@ -86,37 +117,38 @@ fn setup(
         transform: Transform::from_rotation(Quat::from_euler(EulerRot::XYZ, -PI/4.0, -PI/4.0, 0.0)),
         ..default()
     });
-
-    cmd.spawn((
-        PbrBundle {
-            mesh: meshes.add(Sphere::default()),
-            material: materials.add(Color::WHITE),
-            transform: Transform::from_xyz(0.0, 2.0, 0.0),
-            ..default()
-        },
-        PlayerPhysicsBundle::default(),
-        PlayerBundle::default(),
-        CameraTarget,
-    ))
-    .insert(CoinCollection { num: 0 });
-
-    // spawn practice target
-    cmd.spawn((
-        PbrBundle {
-            mesh: meshes.add(Cuboid::default()),
-            material: materials.add(Color::WHITE),
-            transform: Transform::from_xyz(3.0, 2.0, 0.0),
-            ..default()
-        },
-        PlayerPhysicsBundle::default(),
-        PlayerBundle::default(),
-    ))
-    .insert(PracticeBox::DoNothing);
 }
 
-#[derive(Component)]
-enum PracticeBox {
-    DoNothing,
+fn spawn_players(
+    mut cmd: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    local_players: Res<LocalPlayers>,
+    assignments: Res<GamepadAssignments>,
+    config: Res<KeyBindingsConfig>,
+    graph: Res<NavGraph>,
+) {
+    let mesh = meshes.add(Sphere::default());
+    // NOTE: players start on standing land spread over the island, the practice box takes the last spot
+    let spawn_points = graph.spawn_points(local_players.num + 1);
+    for slot in 0..local_players.num {
+        let ground = spawn_points.get(slot).copied().unwrap_or_default();
+        let position = ground + Vec3::Y * 1.5;
+        cmd.spawn((
+            PbrBundle {
+                mesh: mesh.clone(),
+                material: materials.add(Color::WHITE),
+                transform: Transform::from_translation(position),
+                ..default()
+            },
+            PlayerPhysicsBundle::default(),
+            PlayerBundle::default(),
+            PlayerSlot(slot),
+            InputDevice::for_slot(slot, &assignments, &config),
+            CameraTarget,
+        ))
+        .insert(CoinCollection { num: 0 });
+    }
 }
 
 fn player_move(
@@ -126,42 +158,25 @@ fn player_move(
             &mut ExternalImpulse,
             &Grounded,
             &PlayerActionState,
+            &PlayerInput,
         ),
-        (With<Player>, Without<PracticeBox>),
+        With<Player>,
     >,
-    keys: Res<ButtonInput<KeyCode>>,
-    key_bindings: Res<KeyBindings>,
     values: Res<PlayerActionValues>,
 ) {
-    let mut force = Vec3::ZERO;
-    let forward = -Vec3::Z;
-    let right = Vec3::X;
-    for &key in keys.get_pressed() {
-        if key == key_bindings.move_forward {
-            force += forward;
-        }
-        if key == key_bindings.move_backward {
-            force -= forward;
-        }
-        if key == key_bindings.move_right {
-            force += right;
+    for (mut ext_force, mut ext_impulse, grounded, state, input) in query.iter_mut() {
+        let force = input.move_direction();
+        let mut jump_impulse = Vec3::ZERO;
+        if input.jump.just_pressed() {
+            jump_impulse = Vec3::new(0., 8.0, 0.0);
         }
-        if key == key_bindings.move_left {
-            force -= right;
-        }
-    }
-
-    force = force.normalize_or_zero();
-    let mut jump_impulse = Vec3::ZERO;
 
-    if keys.just_pressed(key_bindings.move_jump) {
-        jump_impulse = Vec3::new(0., 8.0, 0.0);
-    }
-
-    for (mut ext_force, mut ext_impulse, grounded, state) in query.iter_mut() {
         match grounded {
             Grounded::Grounded => {
                 ext_force.force = force * values.move_multipier(state);
+                if let PlayerActionState::Stunned(_) = state {
+                    continue;
+                }
                 ext_impulse.impulse += jump_impulse;
             }
             Grounded::Airborne => {
@@ -171,16 +186,47 @@ fn player_move(
     }
 }
 
+// NOTE: the tile under the player changes its movement, e.g. mud slows it down
+fn apply_ground_effects(
+    mut query: Query<(&mut ExternalForce, &mut Damping, &Grounded, &GroundTile), With<Player>>,
+    map: Res<WorldMap>,
+) {
+    for (mut ext_force, mut damping, grounded, ground) in query.iter_mut() {
+        let kind = match grounded {
+            Grounded::Grounded => ground
+                .0
+                .and_then(|handle| map.get(&handle))
+                .map(|tile| tile.kind),
+            Grounded::Airborne => None,
+        };
+        ext_force.force *= kind.map_or(1.0, |kind| kind.move_multiplier());
+        damping.linear_damping = kind.map_or(0.0, |kind| kind.linear_damping());
+    }
+}
+
 fn check_player_grounded(
-    mut query: Query<(&mut Grounded, &Transform, Entity), With<Player>>,
+    mut query: Query<
+        (
+            &mut Grounded,
+            &mut GroundTile,
+            &Transform,
+            &Velocity,
+            Entity,
+        ),
+        With<Player>,
+    >,
+    tiles: TileLookup,
     rapier: Res<RapierContext>,
+    mut landed_events: EventWriter<PlayerLanded>,
 ) {
     let grounding_threshold = 0.55;
-    for (mut grounded, trans, entity) in query.iter_mut() {
+    for (mut grounded, mut ground, trans, velocity, entity) in query.iter_mut() {
+        let was_airborne = matches!(*grounded, Grounded::Airborne);
         *grounded = Grounded::Airborne;
+        ground.0 = None;
         let ray_pos = trans.translation;
         let ray_dir = -Vec3::Y;
-        let Some((_entity, toi)) = rapier.cast_ray(
+        let Some((hit, intersection)) = rapier.cast_ray_and_get_normal(
             ray_pos,
             ray_dir,
             bevy_rapier3d::prelude::Real::MAX,
@@ -189,9 +235,20 @@ fn check_player_grounded(
         ) else {
             continue;
         };
-        let dist = (ray_dir * toi).length();
-        if dist < grounding_threshold {
+        let dist = (ray_dir * intersection.toi).length();
+        // NOTE: on ramps the ground below the ball is further away than on flat tiles
+        let slope = intersection.normal.y.max(0.5);
+        if dist < grounding_threshold / slope {
             *grounded = Grounded::Grounded;
+            ground.0 = tiles.tile_at(hit, intersection.point);
+            if was_airborne {
+                landed_events.send(PlayerLanded {
+                    player: entity,
+                    tile: ground.0,
+                    position: trans.translation - Vec3::Y * dist,
+                    speed: -velocity.linvel.y,
+                });
+            }
         }
     }
 }
@@ -203,28 +260,28 @@ fn player_action_system(
             &Grounded,
             &ExternalForce,
             &mut ExternalImpulse,
+            &PlayerInput,
         ),
-        (With<Player>, Without<PracticeBox>),
+        With<Player>,
     >,
-    keys: Res<ButtonInput<KeyCode>>,
-    key_bindings: Res<KeyBindings>,
     time: Res<Time>,
     values: Res<PlayerActionValues>,
 ) {
-    for (mut state, grounded, force, mut impulse) in query.iter_mut() {
+    for (mut state, grounded, force, mut impulse, input) in query.iter_mut() {
         let temp_state = state.clone();
         match (grounded, temp_state) {
             (Grounded::Grounded, PlayerActionState::Idle) => {
-                if keys.just_pressed(key_bindings.attack) {
+                if input.attack.just_pressed() {
                     *state = PlayerActionState::PrepareAttack(Duration::from_secs(0))
                 }
-                if keys.just_pressed(key_bindings.shield) {
+                if input.shield.just_pressed() {
                     *state = PlayerActionState::Shield(Duration::from_secs(0))
                 }
             }
             (Grounded::Grounded, PlayerActionState::PrepareAttack(dur)) => {
                 let new_dur = time.delta() + dur;
-                if keys.pressed(key_bindings.attack) && new_dur < values.max_prepare_duration {
+                // NOTE: the attack is released with the button or once it is fully charged
+                if !input.attack.just_released() && new_dur < values.max_prepare_duration {
                     *state = PlayerActionState::PrepareAttack(new_dur);
                     continue;
                 }
@@ -245,14 +302,35 @@ fn player_action_system(
                 }
                 *state = PlayerActionState::Attack(new_dur, strength);
             }
+            (_, PlayerActionState::Stunned(dur)) => {
+                let new_dur = time.delta() + dur;
+                if new_dur > values.stun_duration {
+                    *state = PlayerActionState::Idle;
+                    continue;
+                }
+                *state = PlayerActionState::Stunned(new_dur);
+            }
             (Grounded::Grounded, PlayerActionState::Shield(dur)) => {
                 let new_dur = time.delta() + dur;
-                if keys.pressed(key_bindings.shield) || new_dur < values.min_shield_duration {
+                if new_dur > values.max_shield_duration {
+                    info!("Shield broke after {:?}.", new_dur);
+                    *state = PlayerActionState::Stunned(Duration::from_secs(0));
+                    continue;
+                }
+                if input.shield.pressed() || new_dur < values.min_shield_duration {
                     *state = PlayerActionState::Shield(new_dur);
                     continue;
                 }
                 *state = PlayerActionState::Idle;
             }
+            (Grounded::Airborne, PlayerActionState::Idle) => {
+                if input.attack.just_pressed() {
+                    *state = PlayerActionState::GroundPound;
+                    impulse.impulse += Vec3::NEG_Y * values.ground_pound_impulse;
+                }
+            }
+            // NOTE: the ground pound ends when the player lands, see `ground_impact_system`
+            (_, PlayerActionState::GroundPound) => {}
             (Grounded::Airborne, _) => {
                 // info!("ActionState changes for airborne not jet implemented!")
             }
@@ -260,35 +338,120 @@ fn player_action_system(
     }
 }
 
+// NOTE: ground pounds, heavy landings and charged attacks damage the tiles they hit
+fn ground_impact_system(
+    mut query: Query<(&mut PlayerActionState, &GroundTile, &Transform), With<Player>>,
+    mut landed_events: EventReader<PlayerLanded>,
+    mut impact_events: EventWriter<TileImpact>,
+    values: Res<PlayerActionValues>,
+) {
+    for landed in landed_events.read() {
+        let Ok((mut state, _, _)) = query.get_mut(landed.player) else {
+            continue;
+        };
+        if let PlayerActionState::GroundPound = *state {
+            *state = PlayerActionState::Idle;
+            impact_events.send(TileImpact {
+                handle: landed.tile,
+                position: landed.position,
+                damage: values.ground_pound_damage,
+                radius: values.ground_pound_radius,
+            });
+            continue;
+        }
+        if landed.speed > values.heavy_landing_speed {
+            impact_events.send(TileImpact {
+                handle: landed.tile,
+                position: landed.position,
+                damage: values.landing_damage * landed.speed,
+                radius: 0.0,
+            });
+        }
+    }
+
+    // a charged attack pushes off the ground in the frame it is released
+    for (state, ground, trans) in query.iter() {
+        let PlayerActionState::Attack(dur, strength) = *state else {
+            continue;
+        };
+        if !dur.is_zero() || strength <= 0.0 {
+            continue;
+        }
+        impact_events.send(TileImpact {
+            handle: ground.0,
+            position: trans.translation,
+            damage: values.attack_ground_damage * strength,
+            radius: 0.0,
+        });
+    }
+}
+
+// NOTE: players spend coins to raise a tile next to the land they stand on, in front of them
+fn raise_tile_system(
+    mut query: Query<(&PlayerInput, &GroundTile, &Transform, &mut CoinCollection), With<Player>>,
+    mut raise_events: EventWriter<RaiseTile>,
+    map: Res<WorldMap>,
+    des: Res<MapDescriptor>,
+) {
+    let Some(cost) = des.raise_cost else {
+        return;
+    };
+    for (input, ground, trans, mut coins) in query.iter_mut() {
+        if !input.raise.just_pressed() || coins.num < cost {
+            continue;
+        }
+        let Some(from) = ground.0 else {
+            continue;
+        };
+        let towards = trans.translation + input.move_direction() * 2.0;
+        let Some(handle) = des.raise_target(&map, from, towards) else {
+            continue;
+        };
+        coins.num -= cost;
+        raise_events.send(RaiseTile { handle });
+    }
+}
+
 // FIXME: Apply Airborne penalty
 fn player_collision_system(
     mut collision_events: EventReader<CollisionEvent>,
     mut query: Query<
         (
             &mut ExternalImpulse,
-            &PlayerActionState,
-            &Grounded,
+            &mut PlayerActionState,
             &Transform,
+            &mut DamagePercent,
         ),
         With<Player>,
     >,
+    invulnerable: Query<(), With<Invulnerable>>,
+    mut hit_events: EventWriter<PlayerHit>,
+    values: Res<PlayerActionValues>,
 ) {
-    use Grounded as G;
     use PlayerActionState as PAS;
     for collision_event in collision_events.read() {
         println!("Received collision event: {:?}", collision_event);
         let CollisionEvent::Started(e0, e1, _) = collision_event else {
             continue;
         };
+        let mut stunned = None;
+        let mut hits = Vec::new();
         let (imp0, imp1) = {
-            let Ok((_, pas0, _, trans0)) = query.get(*e0) else {
+            let Ok((_, pas0, trans0, _)) = query.get(*e0) else {
                 continue;
             };
-            let Ok((_, pas1, _, trans1)) = query.get(*e1) else {
+            let Ok((_, pas1, trans1, _)) = query.get(*e1) else {
                 continue;
             };
             let dir0 = trans0.translation - trans1.translation;
             let dir1 = trans1.translation - trans0.translation;
+            let blocked = 24.0 * (1.0 - values.shield_absorption);
+            if let PAS::Attack(_, strength) = pas0 {
+                hits.push((*e0, *e1, *strength, matches!(pas1, PAS::Shield(_))));
+            }
+            if let PAS::Attack(_, strength) = pas1 {
+                hits.push((*e1, *e0, *strength, matches!(pas0, PAS::Shield(_))));
+            }
 
             match (pas0, pas1) {
                 (PAS::Idle, PAS::Idle) => {
@@ -307,28 +470,74 @@ fn player_collision_system(
                     info!("Entity {:?} has attacked Entity {:?} ", e1, e0);
                     (2.0 * dir0, 24.0 * dir1)
                 }
+                // NOTE: a shield raised within the parry window reflects the attack
+                (PAS::Shield(dur), PAS::Attack(_, _)) if *dur < values.parry_duration => {
+                    info!("Entity {:?} has parried Entity {:?} ", e0, e1);
+                    stunned = Some(*e1);
+                    (Vec3::ZERO, 24.0 * dir1)
+                }
+                (PAS::Attack(_, _), PAS::Shield(dur)) if *dur < values.parry_duration => {
+                    info!("Entity {:?} has parried Entity {:?} ", e1, e0);
+                    stunned = Some(*e0);
+                    (24.0 * dir0, Vec3::ZERO)
+                }
                 (PAS::Shield(_), PAS::Attack(_, _)) => {
-                    info!("Entity {:?} has attacked Entity {:?} ", e1, e0);
-                    unimplemented!();
+                    info!("Entity {:?} has blocked Entity {:?} ", e0, e1);
+                    (blocked * dir0, 3.0 * dir1)
                 }
                 (PAS::Attack(_, _), PAS::Shield(_)) => {
-                    info!("Entity {:?} has attacked Entity {:?} ", e1, e0);
-                    unimplemented!();
+                    info!("Entity {:?} has blocked Entity {:?} ", e1, e0);
+                    (3.0 * dir0, blocked * dir1)
+                }
+                // NOTE: a stunned player is open for punishment and takes the full hit
+                (PAS::Stunned(_), PAS::Attack(_, _)) => {
+                    info!("Entity {:?} has punished Entity {:?} ", e1, e0);
+                    (24.0 * dir0, 3.0 * dir1)
+                }
+                (PAS::Attack(_, _), PAS::Stunned(_)) => {
+                    info!("Entity {:?} has punished Entity {:?} ", e0, e1);
+                    (3.0 * dir0, 24.0 * dir1)
                 }
                 (_, _) => (Vec3::ZERO, Vec3::ZERO),
             }
         };
+        // NOTE: players on the respawn platform can not be pushed off it
+        let imp0 = if invulnerable.contains(*e0) {
+            Vec3::ZERO
+        } else {
+            imp0
+        };
+        let imp1 = if invulnerable.contains(*e1) {
+            Vec3::ZERO
+        } else {
+            imp1
+        };
+        // a parried attack pushes nobody and deals no damage
+        let hits: Vec<_> = hits
+            .into_iter()
+            .filter(|(_, target, _, _)| {
+                let impulse = if target == e0 { imp0 } else { imp1 };
+                impulse != Vec3::ZERO
+            })
+            .collect();
+        for (_, target, strength, shielded) in hits.iter() {
+            query.get_mut(*target).unwrap().3 .0 += values.hit_damage(*strength, *shielded);
+        }
+        // knockback grows with the damage taken, including the hit that was just dealt
+        let imp0 = imp0 * values.knockback_scale(query.get(*e0).unwrap().3);
+        let imp1 = imp1 * values.knockback_scale(query.get(*e1).unwrap().3);
         query.get_mut(*e0).unwrap().0.impulse += imp0;
         query.get_mut(*e1).unwrap().0.impulse += imp1;
-    }
-}
-
-// FIXME: This should reset the game and not just print a message
-fn check_game_over(query: Query<&Transform, With<Player>>) {
-    for trans in query.iter() {
-        if trans.translation.y > 0.0 {
-            continue;
+        for (attacker, target, strength, _) in hits {
+            hit_events.send(PlayerHit {
+                attacker,
+                target,
+                impulse: if target == *e0 { imp0 } else { imp1 },
+                strength,
+            });
+        }
+        if let Some(entity) = stunned {
+            *query.get_mut(entity).unwrap().1 = PAS::Stunned(Duration::from_secs(0));
         }
-        info!("Game Over!",)
     }
 }
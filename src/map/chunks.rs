@@ -0,0 +1,318 @@
+use bevy::{
+    ecs::system::{EntityCommands, SystemParam},
+    prelude::*,
+    render::{
+        mesh::{Indices, PrimitiveTopology, VertexAttributeValues},
+        render_asset::RenderAssetUsages,
+    },
+    utils::HashMap,
+};
+use bevy_rapier3d::{dynamics::RigidBody, geometry::Collider};
+
+use super::{
+    spawn_tile,
+    terrain::{TileAssets, TileShape},
+    visuals::TileMaterials,
+    MapDescriptor, MapTile, MapTileHandle, TileDamaged, TileKind, TileWarning, WorldMap,
+};
+
+// NOTE: chunks span this many tiles along both grid axes
+pub const CHUNK_SIZE: i32 = 8;
+
+// NOTE: merged static tiles of one kind, tiles leave the chunk once they need to move on their own
+#[derive(Component)]
+pub struct MapChunk;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct ChunkKey {
+    x: i32,
+    z: i32,
+    kind: TileKind,
+}
+
+impl ChunkKey {
+    fn new(handle: MapTileHandle, kind: TileKind) -> Self {
+        Self {
+            x: handle.x.div_euclid(CHUNK_SIZE),
+            z: handle.z.div_euclid(CHUNK_SIZE),
+            kind,
+        }
+    }
+}
+
+struct Chunk {
+    entity: Entity,
+    // damage stage of every tile when the chunk was last built
+    tiles: HashMap<MapTileHandle, usize>,
+    dirty: bool,
+}
+
+// NOTE: entities of the current map, either a chunk or a tile of its own
+#[derive(Resource, Default)]
+pub struct MapEntities {
+    chunks: HashMap<ChunkKey, Chunk>,
+    tiles: HashMap<MapTileHandle, Entity>,
+}
+
+impl MapEntities {
+    pub fn num_chunks(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn num_tiles(&self) -> usize {
+        self.tiles.len()
+    }
+
+    pub(super) fn add_to_chunk(
+        &mut self,
+        cmd: &mut Commands,
+        handle: MapTileHandle,
+        tile: &MapTile,
+    ) {
+        let chunk = self
+            .chunks
+            .entry(ChunkKey::new(handle, tile.kind))
+            .or_insert_with(|| Chunk {
+                entity: cmd
+                    .spawn(SpatialBundle::default())
+                    .insert(MapChunk)
+                    .insert(RigidBody::Fixed)
+                    .insert(tile.kind.friction())
+                    .insert(tile.kind.restitution())
+                    .id(),
+                tiles: HashMap::new(),
+                dirty: true,
+            });
+        chunk.tiles.insert(handle, tile.damage_stage());
+        chunk.dirty = true;
+    }
+
+    pub(super) fn insert_tile(&mut self, handle: MapTileHandle, entity: Entity) {
+        self.tiles.insert(handle, entity);
+    }
+
+    pub(super) fn remove_tile(&mut self, handle: MapTileHandle) -> Option<Entity> {
+        self.tiles.remove(&handle)
+    }
+
+    fn tile_entity(
+        &mut self,
+        cmd: &mut Commands,
+        shape: &TileShape,
+        tile_materials: &TileMaterials,
+        handle: MapTileHandle,
+        tile: &MapTile,
+    ) -> Entity {
+        if let Some(&entity) = self.tiles.get(&handle) {
+            return entity;
+        }
+        if let Some(chunk) = self.chunks.get_mut(&ChunkKey::new(handle, tile.kind)) {
+            chunk.tiles.remove(&handle);
+            chunk.dirty = true;
+        }
+        let entity = spawn_tile(cmd, shape, tile_materials, handle, tile);
+        self.tiles.insert(handle, entity);
+        entity
+    }
+
+    pub(super) fn despawn_all(&mut self, cmd: &mut Commands) {
+        let chunks = self.chunks.drain().map(|(_, chunk)| chunk.entity);
+        for entity in chunks.chain(self.tiles.drain().map(|(_, entity)| entity)) {
+            cmd.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+// NOTE: copies of the tile meshes moved to their positions, merged into a single mesh
+fn merged_mesh(parts: &[(&Mesh, Vec3)]) -> Mesh {
+    let mut vertices = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+    let mut indices = Vec::new();
+    for (base, position) in parts {
+        let Some(VertexAttributeValues::Float32x3(base_positions)) =
+            base.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            continue;
+        };
+        let base_normals = match base.attribute(Mesh::ATTRIBUTE_NORMAL) {
+            Some(VertexAttributeValues::Float32x3(normals)) => normals.clone(),
+            _ => vec![[0.0, 1.0, 0.0]; base_positions.len()],
+        };
+        let base_uvs = match base.attribute(Mesh::ATTRIBUTE_UV_0) {
+            Some(VertexAttributeValues::Float32x2(uvs)) => uvs.clone(),
+            _ => vec![[0.0, 0.0]; base_positions.len()],
+        };
+        let base_indices: Vec<u32> = match base.indices() {
+            Some(indices) => indices.iter().map(|i| i as u32).collect(),
+            None => (0..base_positions.len() as u32).collect(),
+        };
+        let start = vertices.len() as u32;
+        vertices.extend(
+            base_positions
+                .iter()
+                .map(|v| (Vec3::from(*v) + *position).to_array()),
+        );
+        normals.extend_from_slice(&base_normals);
+        uvs.extend_from_slice(&base_uvs);
+        indices.extend(base_indices.iter().map(|i| start + i));
+    }
+
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, vertices)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+    .with_inserted_indices(Indices::U32(indices))
+}
+
+// NOTE: rebuilds the meshes and the compound collider of chunks whose tiles changed
+pub(super) fn rebuild_chunks(
+    mut cmd: Commands,
+    mut entities: ResMut<MapEntities>,
+    map: Res<WorldMap>,
+    assets: Option<Res<TileAssets>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    tile_materials: Res<TileMaterials>,
+) {
+    let Some(assets) = assets else {
+        return;
+    };
+    entities.chunks.retain(|key, chunk| {
+        if !chunk.dirty {
+            return true;
+        }
+        chunk.dirty = false;
+        if chunk.tiles.is_empty() {
+            cmd.entity(chunk.entity).despawn_recursive();
+            return false;
+        }
+
+        let mut stages: HashMap<usize, Vec<(&Mesh, Vec3)>> = HashMap::new();
+        let mut shapes = Vec::with_capacity(chunk.tiles.len());
+        for (handle, stage) in chunk.tiles.iter_mut() {
+            let Some(tile) = map.get(handle) else {
+                continue;
+            };
+            let Some(shape) = assets.get(tile) else {
+                continue;
+            };
+            *stage = tile.damage_stage();
+            stages
+                .entry(*stage)
+                .or_default()
+                .push((&shape.base_mesh, tile.position));
+            shapes.push((tile.position, Quat::IDENTITY, shape.collider.clone()));
+        }
+
+        let mut chunk_cmd = cmd.entity(chunk.entity);
+        chunk_cmd
+            .despawn_descendants()
+            .insert(Collider::compound(shapes));
+        chunk_cmd.with_children(|parent| {
+            for (stage, parts) in stages {
+                parent.spawn(PbrBundle {
+                    mesh: meshes.add(merged_mesh(&parts)),
+                    material: tile_materials.get(key.kind, stage),
+                    ..default()
+                });
+            }
+        });
+        true
+    });
+}
+
+// NOTE: chunks are rebuilt when one of their tiles reaches the next damage stage
+pub(super) fn mark_damaged_chunks(
+    mut events: EventReader<TileDamaged>,
+    mut entities: ResMut<MapEntities>,
+    map: Res<WorldMap>,
+) {
+    for event in events.read() {
+        let Some(tile) = map.get(&event.handle) else {
+            continue;
+        };
+        let Some(chunk) = entities
+            .chunks
+            .get_mut(&ChunkKey::new(event.handle, tile.kind))
+        else {
+            continue;
+        };
+        if chunk
+            .tiles
+            .get(&event.handle)
+            .is_some_and(|stage| *stage != tile.damage_stage())
+        {
+            chunk.dirty = true;
+        }
+    }
+}
+
+// NOTE: gives tiles an entity of their own, splitting them out of their chunk if necessary
+#[derive(SystemParam)]
+pub(super) struct TileSplitter<'w, 's> {
+    cmd: Commands<'w, 's>,
+    entities: ResMut<'w, MapEntities>,
+    assets: ResMut<'w, TileAssets>,
+    meshes: ResMut<'w, Assets<Mesh>>,
+    tile_materials: Res<'w, TileMaterials>,
+}
+
+impl<'w, 's> TileSplitter<'w, 's> {
+    pub fn split(&mut self, handle: MapTileHandle, tile: &MapTile) -> EntityCommands<'_> {
+        let shape = self.assets.insert(tile, &mut self.meshes);
+        let entity =
+            self.entities
+                .tile_entity(&mut self.cmd, shape, &self.tile_materials, handle, tile);
+        self.cmd.entity(entity)
+    }
+}
+
+// NOTE: tiles about to sink need their own entity to wobble
+pub(super) fn split_warned_tiles(
+    mut events: EventReader<TileWarning>,
+    mut splitter: TileSplitter,
+    map: Res<WorldMap>,
+) {
+    for event in events.read() {
+        let Some(tile) = map.get(&event.handle) else {
+            continue;
+        };
+        splitter.split(event.handle, tile);
+    }
+}
+
+// NOTE: finds the tile belonging to a collider, for chunks the tile is looked up from the hit point
+// at the foot of a plateau the closest tile may be the wrong one, so the surface height decides
+#[derive(SystemParam)]
+pub struct TileLookup<'w, 's> {
+    tiles: Query<'w, 's, &'static MapTileHandle>,
+    chunks: Query<'w, 's, (), With<MapChunk>>,
+    map: Res<'w, WorldMap>,
+    des: Res<'w, MapDescriptor>,
+}
+
+impl<'w, 's> TileLookup<'w, 's> {
+    pub fn tile_at(&self, entity: Entity, point: Vec3) -> Option<MapTileHandle> {
+        if let Ok(handle) = self.tiles.get(entity) {
+            return Some(*handle);
+        }
+        if !self.chunks.contains(entity) {
+            return None;
+        }
+        let handle = self.des.grid.handle_at(point, self.des.tile_size);
+        let score = |handle: &MapTileHandle| {
+            let tile = self.map.get(handle)?;
+            let dist = tile.position.xz().distance(point.xz());
+            (dist <= self.des.tile_size)
+                .then(|| dist + (self.des.surface_height(tile, point) - point.y).abs())
+        };
+        std::iter::once(handle)
+            .chain(self.des.grid.neighbours(handle))
+            .filter_map(|handle| Some((handle, score(&handle)?)))
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(handle, _)| handle)
+    }
+}
@@ -0,0 +1,106 @@
+use std::collections::VecDeque;
+
+use bevy::{prelude::*, utils::HashSet};
+
+use super::{
+    grid::GridKind, MapDescriptor, MapTile, MapTileHandle, TileEvents, TileSinking, WorldMap,
+};
+
+// NOTE: sent once for every piece of land that lost its connection to the main island
+#[derive(Event, Clone, Debug)]
+pub struct FragmentSevered {
+    pub tiles: Vec<MapTileHandle>,
+    pub center: Vec3,
+}
+
+// NOTE: standing tiles at the last check, `None` until the first check of a round
+#[derive(Resource, Default)]
+pub(super) struct StandingTiles(Option<usize>);
+
+// NOTE: flood fills the standing land into groups of connected tiles, platforms are left out
+pub fn islands(map: &WorldMap, grid: GridKind) -> Vec<HashSet<MapTileHandle>> {
+    let mut visited = HashSet::new();
+    let mut islands = Vec::new();
+    for (&start, tile) in map.iter() {
+        if !tile.is_land() || visited.contains(&start) {
+            continue;
+        }
+        let mut island = HashSet::new();
+        let mut queue = VecDeque::from([start]);
+        while let Some(handle) = queue.pop_front() {
+            let standing = map.get(&handle).is_some_and(MapTile::is_land);
+            if !standing || !visited.insert(handle) {
+                continue;
+            }
+            island.insert(handle);
+            queue.extend(grid.neighbours(handle));
+        }
+        islands.push(island);
+    }
+    islands
+}
+
+// NOTE: the main island holds the anchor tile, once the anchor is gone it is the largest island
+fn main_island(islands: &[HashSet<MapTileHandle>], anchor: Option<MapTileHandle>) -> Option<usize> {
+    anchor
+        .and_then(|anchor| islands.iter().position(|island| island.contains(&anchor)))
+        .or_else(|| {
+            islands
+                .iter()
+                .enumerate()
+                .max_by_key(|(_, island)| island.len())
+                .map(|(i, _)| i)
+        })
+}
+
+pub(super) fn reset_standing_tiles(mut standing: ResMut<StandingTiles>) {
+    standing.0 = None;
+}
+
+// NOTE: only runs the flood fill when tiles started sinking since the last check
+pub(super) fn sink_severed_fragments(
+    mut map: ResMut<WorldMap>,
+    des: Res<MapDescriptor>,
+    mut standing: ResMut<StandingTiles>,
+    mut events: EventWriter<FragmentSevered>,
+    mut tile_events: TileEvents,
+) {
+    let mut count = map
+        .values()
+        .filter(|tile| tile.sinking == TileSinking::NotSinking)
+        .count();
+    let changed = match standing.0 {
+        Some(standing) => count < standing,
+        None => true,
+    };
+    standing.0 = Some(count);
+    if !changed {
+        return;
+    }
+
+    let islands = islands(&map, des.grid);
+    let Some(main) = main_island(&islands, des.anchor) else {
+        return;
+    };
+    for (i, island) in islands.into_iter().enumerate() {
+        if i == main {
+            continue;
+        }
+        let mut center = Vec3::ZERO;
+        for handle in island.iter() {
+            let Some(tile) = map.get_mut(handle) else {
+                continue;
+            };
+            center += tile.position;
+            tile_events.start_sinking(*handle, tile);
+        }
+        count -= island.len();
+        center /= island.len() as f32;
+        info!("{} tiles got severed from the island!", island.len());
+        events.send(FragmentSevered {
+            tiles: island.into_iter().collect(),
+            center,
+        });
+    }
+    standing.0 = Some(count);
+}
@@ -0,0 +1,153 @@
+use bevy::{prelude::*, time::Stopwatch};
+use rand::{
+    seq::{IteratorRandom, SliceRandom},
+    Rng,
+};
+use serde::Deserialize;
+
+use super::{LeafTiles, MapDescriptor, MapTileHandle, TileEvents, TileSinking, WorldMap};
+
+// NOTE: decides which leaf tile loses constitution each frame and how fast
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+pub enum ErosionStrategy {
+    #[default]
+    Random,
+    // the outermost ring of the island erodes first
+    Rings,
+    // erosion sweeps over the island, coming from the side at `angle` degrees
+    Wave {
+        angle: f32,
+    },
+    // tiles close to the leading player erode more likely
+    Hunt,
+    Timeline(Vec<TimelineStep>),
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct TimelineStep {
+    // seconds since the round started
+    pub at: f32,
+    pub strategy: ErosionStrategy,
+    #[serde(default = "default_rate")]
+    pub rate: f32,
+}
+
+fn default_rate() -> f32 {
+    1.0
+}
+
+// NOTE: position the hunt strategy erodes towards, kept up to date by the round
+#[derive(Resource, Default)]
+pub struct ErosionFocus(pub Option<Vec3>);
+
+#[derive(Resource, Default, Deref, DerefMut)]
+pub(super) struct ErosionClock(Stopwatch);
+
+impl ErosionStrategy {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ErosionStrategy::Random => "Random",
+            ErosionStrategy::Rings => "Rings",
+            ErosionStrategy::Wave { .. } => "Wave",
+            ErosionStrategy::Hunt => "Hunt",
+            ErosionStrategy::Timeline(_) => "Timeline",
+        }
+    }
+
+    // NOTE: resolves a timeline to the step active at `elapsed`, together with its rate
+    fn active(&self, elapsed: f32) -> (&ErosionStrategy, f32) {
+        let ErosionStrategy::Timeline(steps) = self else {
+            return (self, 1.0);
+        };
+        let Some(step) = steps.iter().rev().find(|step| step.at <= elapsed) else {
+            return (&ErosionStrategy::Random, 0.0);
+        };
+        let (strategy, rate) = step.strategy.active(elapsed - step.at);
+        (strategy, rate * step.rate)
+    }
+
+    fn pick(
+        &self,
+        candidates: &[(MapTileHandle, Vec3)],
+        focus: Option<Vec3>,
+        tile_size: f32,
+        rng: &mut impl Rng,
+    ) -> Option<MapTileHandle> {
+        // NOTE: picks randomly among the tiles scoring within one tile of the best score
+        let pick_front = |score: &dyn Fn(Vec3) -> f32, rng: &mut _| {
+            let best = candidates
+                .iter()
+                .map(|(_, pos)| score(*pos))
+                .fold(f32::MIN, f32::max);
+            candidates
+                .iter()
+                .filter(|(_, pos)| score(*pos) >= best - tile_size)
+                .map(|(handle, _)| *handle)
+                .choose(rng)
+        };
+        match self {
+            ErosionStrategy::Random | ErosionStrategy::Timeline(_) => {
+                candidates.choose(rng).map(|(handle, _)| *handle)
+            }
+            ErosionStrategy::Rings => pick_front(&|pos| pos.xz().length(), rng),
+            ErosionStrategy::Wave { angle } => {
+                let from = Vec2::from_angle(angle.to_radians());
+                pick_front(&|pos| pos.xz().dot(from), rng)
+            }
+            ErosionStrategy::Hunt => {
+                let Some(focus) = focus else {
+                    return candidates.choose(rng).map(|(handle, _)| *handle);
+                };
+                candidates
+                    .choose_weighted(rng, |(_, pos)| {
+                        let dist = pos.xz().distance(focus.xz()) / tile_size;
+                        1.0 / (1.0 + dist * dist)
+                    })
+                    .ok()
+                    .map(|(handle, _)| *handle)
+            }
+        }
+    }
+}
+
+pub(super) fn reset_erosion_clock(mut clock: ResMut<ErosionClock>) {
+    clock.reset();
+}
+
+// NOTE: select a leaf according to the erosion strategy and reduce its constitution
+pub(super) fn reduce_leaf_constitution(
+    mut map: ResMut<WorldMap>,
+    leafs: Res<LeafTiles>,
+    time: Res<Time>,
+    des: Res<MapDescriptor>,
+    focus: Res<ErosionFocus>,
+    mut clock: ResMut<ErosionClock>,
+    mut events: TileEvents,
+) {
+    clock.tick(time.delta());
+    let (strategy, rate) = des.erosion.active(clock.elapsed_secs());
+    if rate <= 0.0 {
+        return;
+    }
+    let candidates: Vec<(MapTileHandle, Vec3)> = leafs
+        .iter()
+        .filter_map(|handle| {
+            let tile = map.get(handle)?;
+            (tile.sinking == TileSinking::NotSinking).then_some((*handle, tile.position))
+        })
+        .collect();
+    let Some(handle) = strategy.pick(&candidates, focus.0, des.tile_size, &mut rand::thread_rng())
+    else {
+        return;
+    };
+    let Some(leaf) = map.get_mut(&handle) else {
+        error!("Unable to find a Leaf in Map!");
+        return;
+    };
+    // NOTE: raised tiles are further from the water and erode slower
+    let shelter = 1.0 + leaf.elevation.max(0.0) / des.tile_height;
+    let damage =
+        des.constitution_reduction_rate * rate * leaf.kind.decay_rate() * time.delta_seconds()
+            / shelter;
+    events.damage(handle, leaf, damage, des.warning_constitution);
+}
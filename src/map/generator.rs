@@ -0,0 +1,314 @@
+use std::{collections::VecDeque, f32::consts::PI};
+
+use bevy::{
+    math::vec2,
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::{layout::LayoutTile, terrain::Ramp, MapTileHandle, TileKind};
+
+#[derive(Clone, Debug)]
+pub struct GeneratorParams {
+    pub seed: u64,
+    pub coast_noise: f32,
+    pub noise_scale: f32,
+    pub lagoons: u32,
+    pub lagoon_radius: f32,
+    pub islets: u32,
+    pub islet_radius: f32,
+    pub plateau_levels: u32,
+    pub plateau_height: f32,
+    // NOTE: chance of a tile below a plateau edge becoming a ramp up to it
+    pub ramp_chance: f64,
+    pub spawn_radius: i32,
+    pub tiles_per_player: usize,
+}
+
+impl Default for GeneratorParams {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            coast_noise: 0.35,
+            noise_scale: 0.18,
+            lagoons: 1,
+            lagoon_radius: 1.8,
+            islets: 2,
+            islet_radius: 2.2,
+            plateau_levels: 2,
+            plateau_height: 1.0,
+            ramp_chance: 0.3,
+            spawn_radius: 3,
+            tiles_per_player: 24,
+        }
+    }
+}
+
+// NOTE: smooth 2d value noise in [0, 1], the lattice values are derived from the seed
+struct ValueNoise {
+    seed: u64,
+}
+
+impl ValueNoise {
+    fn lattice(&self, x: i32, z: i32) -> f32 {
+        // splitmix64 over the seeded lattice coordinates
+        let mut h = self.seed
+            ^ (x as i64 as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+            ^ (z as i64 as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+        h = (h ^ (h >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        h = (h ^ (h >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        h ^= h >> 31;
+        (h >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    fn sample(&self, x: f32, z: f32) -> f32 {
+        let (x0, z0) = (x.floor(), z.floor());
+        let (tx, tz) = (x - x0, z - z0);
+        let (sx, sz) = (tx * tx * (3.0 - 2.0 * tx), tz * tz * (3.0 - 2.0 * tz));
+        let (ix, iz) = (x0 as i32, z0 as i32);
+        let a = self.lattice(ix, iz);
+        let b = self.lattice(ix + 1, iz);
+        let c = self.lattice(ix, iz + 1);
+        let d = self.lattice(ix + 1, iz + 1);
+        let top = a + (b - a) * sx;
+        let bottom = c + (d - c) * sx;
+        top + (bottom - top) * sz
+    }
+
+    fn fbm(&self, x: f32, z: f32) -> f32 {
+        let mut value = 0.0;
+        let mut amplitude = 0.5;
+        let mut frequency = 1.0;
+        let mut total = 0.0;
+        for _ in 0..3 {
+            value += amplitude * self.sample(x * frequency, z * frequency);
+            total += amplitude;
+            amplitude *= 0.5;
+            frequency *= 2.0;
+        }
+        value / total
+    }
+}
+
+fn disc(center: Vec2, radius: f32) -> impl Iterator<Item = (i32, i32)> {
+    let r = radius.ceil() as i32;
+    let (cx, cz) = (center.x.round() as i32, center.y.round() as i32);
+    (-r..=r)
+        .flat_map(move |dx| (-r..=r).map(move |dz| (cx + dx, cz + dz)))
+        .filter(move |&(x, z)| vec2(x as f32, z as f32).distance(center) <= radius)
+}
+
+fn neighbours((x, z): (i32, i32)) -> [(i32, i32); 4] {
+    [(x - 1, z), (x + 1, z), (x, z - 1), (x, z + 1)]
+}
+
+// NOTE: flood fill from the origin, everything not reachable from the spawn area is dropped
+fn connected_to_origin(land: &HashSet<(i32, i32)>) -> HashSet<(i32, i32)> {
+    let mut connected = HashSet::new();
+    let mut queue = VecDeque::from([(0, 0)]);
+    while let Some(cell) = queue.pop_front() {
+        if !land.contains(&cell) || !connected.insert(cell) {
+            continue;
+        }
+        queue.extend(neighbours(cell));
+    }
+    connected
+}
+
+fn distance_to_coast(land: &HashSet<(i32, i32)>) -> HashMap<(i32, i32), u32> {
+    let mut distances = HashMap::new();
+    let mut queue = VecDeque::new();
+    for &cell in land.iter() {
+        if neighbours(cell).iter().any(|n| !land.contains(n)) {
+            distances.insert(cell, 0);
+            queue.push_back(cell);
+        }
+    }
+    while let Some(cell) = queue.pop_front() {
+        let next = distances[&cell] + 1;
+        for n in neighbours(cell) {
+            if land.contains(&n) && !distances.contains_key(&n) {
+                distances.insert(n, next);
+                queue.push_back(n);
+            }
+        }
+    }
+    distances
+}
+
+fn islet_centers(params: &GeneratorParams, radius: f32, rng: &mut StdRng) -> Vec<Vec2> {
+    (0..params.islets)
+        .map(|_| {
+            let angle = rng.gen_range(0.0..2.0 * PI);
+            let dist = radius * rng.gen_range(1.3..1.6);
+            Vec2::from_angle(angle) * dist
+        })
+        .collect()
+}
+
+// NOTE: the causeway moves along one axis per step, so it stays connected to the 4 neighbours
+fn causeway(center: Vec2) -> Vec<(i32, i32)> {
+    let (start_x, start_z) = (center.x.round() as i32, center.y.round() as i32);
+    let (mut x, mut z) = (start_x, start_z);
+    let mut cells = vec![(x, z)];
+    while (x, z) != (0, 0) {
+        let step_x = z == 0 || (x != 0 && x.abs() * start_z.abs() >= z.abs() * start_x.abs());
+        if step_x {
+            x -= x.signum();
+        } else {
+            z -= z.signum();
+        }
+        cells.push((x, z));
+    }
+    cells
+}
+
+fn generate_land(params: &GeneratorParams, radius: f32, rng: &mut StdRng) -> HashSet<(i32, i32)> {
+    let coast = ValueNoise { seed: params.seed };
+    let mut land = HashSet::new();
+
+    // noise perturbed coastline
+    for (x, z) in disc(Vec2::ZERO, radius * (1.0 + params.coast_noise)) {
+        let dist = vec2(x as f32, z as f32).length() / radius;
+        let noise = coast.fbm(x as f32 * params.noise_scale, z as f32 * params.noise_scale);
+        if dist + params.coast_noise * (noise - 0.5) * 2.0 < 1.0 {
+            land.insert((x, z));
+        }
+    }
+
+    let islets = islet_centers(params, radius, rng);
+
+    // small lagoons, kept clear of the spawn area
+    let min_lagoon_dist = params.spawn_radius as f32 + params.lagoon_radius + 0.5;
+    let max_lagoon_dist = radius - params.lagoon_radius;
+    for _ in 0..params.lagoons {
+        if max_lagoon_dist <= min_lagoon_dist {
+            break;
+        }
+        let angle = rng.gen_range(0.0..2.0 * PI);
+        let dist = rng.gen_range(min_lagoon_dist..max_lagoon_dist);
+        for cell in disc(Vec2::from_angle(angle) * dist, params.lagoon_radius) {
+            land.remove(&cell);
+        }
+    }
+
+    // satellite islets, each connected to the main island by a causeway
+    // NOTE: laid after the lagoons, so a lagoon can never cut an islet off
+    for center in islets {
+        land.extend(disc(center, params.islet_radius));
+        land.extend(causeway(center));
+    }
+
+    land.extend(disc(Vec2::ZERO, params.spawn_radius as f32));
+    connected_to_origin(&land)
+}
+
+pub fn generate_island(
+    params: &GeneratorParams,
+    radius: u32,
+    num_players: usize,
+) -> Vec<(MapTileHandle, LayoutTile)> {
+    let mut rng = StdRng::seed_from_u64(params.seed);
+    let required = params.tiles_per_player * num_players.max(1);
+    let mut radius = radius.max(params.spawn_radius as u32 + 1) as f32;
+    let mut land = generate_land(params, radius, &mut rng);
+    // NOTE: grow the island until every player has enough room
+    while land.len() < required {
+        radius += 1.0;
+        land = generate_land(params, radius, &mut rng);
+    }
+
+    let heights = ValueNoise {
+        seed: params.seed.wrapping_add(1),
+    };
+    let coast_distance = distance_to_coast(&land);
+    let levels: HashMap<(i32, i32), u32> = land
+        .iter()
+        .map(|&(x, z)| {
+            let coast = coast_distance.get(&(x, z)).copied().unwrap_or(0);
+            let in_spawn = x * x + z * z <= params.spawn_radius * params.spawn_radius;
+            // plateaus stay away from the coast and the spawn area
+            let level = if coast < 2 || in_spawn || params.plateau_levels == 0 {
+                0
+            } else {
+                let noise =
+                    heights.fbm(x as f32 * params.noise_scale, z as f32 * params.noise_scale);
+                let level = ((noise - 0.5) * 2.0 * (params.plateau_levels + 1) as f32).floor();
+                (level.max(0.0) as u32)
+                    .min(params.plateau_levels)
+                    .min(coast - 1)
+            };
+            ((x, z), level)
+        })
+        .collect();
+
+    let mut cells: Vec<_> = levels.iter().map(|(&cell, &level)| (cell, level)).collect();
+    // NOTE: sorted so the ramps only depend on the seed
+    cells.sort();
+    cells
+        .into_iter()
+        .map(|((x, z), level)| {
+            let coast = coast_distance.get(&(x, z)).copied().unwrap_or(0);
+            let kind = match (coast, level) {
+                (0, _) => TileKind::Sand,
+                (_, 0) => TileKind::Grass,
+                _ => TileKind::Rock,
+            };
+            // ramps climb towards a neighbour one level up
+            let ramp = neighbours((x, z))
+                .into_iter()
+                .find(|n| levels.get(n) == Some(&(level + 1)))
+                .filter(|_| rng.gen_bool(params.ramp_chance))
+                .map(|(nx, nz)| Ramp {
+                    angle: ((nz - z) as f32).atan2((nx - x) as f32).to_degrees(),
+                    rise: params.plateau_height,
+                });
+            let tile = LayoutTile {
+                height: level as f32 * params.plateau_height,
+                kind,
+                constitution: 1.0 + level as f32 * 0.5,
+                ramp,
+                platform: None,
+            };
+            (MapTileHandle { x, z }, tile)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEEDS: u64 = 2000;
+
+    #[test]
+    fn islets_survive_the_lagoons() {
+        for seed in 0..SEEDS {
+            let params = GeneratorParams { seed, ..default() };
+            let radius = 8.0;
+            let mut rng = StdRng::seed_from_u64(seed);
+            let islets = islet_centers(&params, radius, &mut rng.clone());
+            assert_eq!(islets.len(), params.islets as usize);
+            let land = generate_land(&params, radius, &mut rng);
+            for center in islets {
+                for cell in disc(center, params.islet_radius) {
+                    assert!(land.contains(&cell), "seed {seed} lost islet cell {cell:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn generated_islands_are_connected() {
+        for seed in 0..SEEDS {
+            let params = GeneratorParams { seed, ..default() };
+            let land: HashSet<(i32, i32)> = generate_island(&params, 8, 3)
+                .into_iter()
+                .map(|(handle, _)| (handle.x, handle.z))
+                .collect();
+            assert!(land.len() >= params.tiles_per_player * 3, "seed {seed}");
+            assert_eq!(connected_to_origin(&land), land, "seed {seed}");
+        }
+    }
+}
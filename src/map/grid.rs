@@ -0,0 +1,194 @@
+use std::f32::consts::PI;
+
+use bevy::{
+    math::vec3,
+    prelude::*,
+    render::{
+        mesh::{Indices, PrimitiveTopology},
+        render_asset::RenderAssetUsages,
+    },
+};
+use bevy_rapier3d::geometry::Collider;
+
+use super::MapTileHandle;
+
+// NOTE: hex tiles use axial coordinates, `x` is q and `z` is r of a pointy-top hex grid
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GridKind {
+    #[default]
+    Square,
+    Hex,
+}
+
+impl GridKind {
+    pub fn neighbours(&self, handle: MapTileHandle) -> Vec<MapTileHandle> {
+        let MapTileHandle { x, z } = handle;
+        match self {
+            GridKind::Square => vec![
+                MapTileHandle { x: x - 1, z },
+                MapTileHandle { x: x + 1, z },
+                MapTileHandle { x, z: z - 1 },
+                MapTileHandle { x, z: z + 1 },
+            ],
+            GridKind::Hex => vec![
+                MapTileHandle { x: x - 1, z },
+                MapTileHandle { x: x + 1, z },
+                MapTileHandle { x, z: z - 1 },
+                MapTileHandle { x, z: z + 1 },
+                MapTileHandle { x: x + 1, z: z - 1 },
+                MapTileHandle { x: x - 1, z: z + 1 },
+            ],
+        }
+    }
+
+    // NOTE: centre of the tile on the xz-plane, neighbouring tiles are `tile_size` apart
+    pub fn position(&self, handle: MapTileHandle, tile_size: f32) -> Vec3 {
+        let (x, z) = (handle.x as f32, handle.z as f32);
+        match self {
+            GridKind::Square => vec3(x * tile_size, 0.0, z * tile_size),
+            GridKind::Hex => vec3(
+                tile_size * (x + z / 2.0),
+                0.0,
+                tile_size * 3f32.sqrt() / 2.0 * z,
+            ),
+        }
+    }
+
+    // NOTE: the tile whose centre is closest to `pos` on the xz-plane, inverse of `position`
+    pub fn handle_at(&self, pos: Vec3, tile_size: f32) -> MapTileHandle {
+        match self {
+            GridKind::Square => MapTileHandle {
+                x: (pos.x / tile_size).round() as i32,
+                z: (pos.z / tile_size).round() as i32,
+            },
+            GridKind::Hex => {
+                let r = pos.z / (tile_size * 3f32.sqrt() / 2.0);
+                let q = pos.x / tile_size - r / 2.0;
+                // cube rounding, the component with the largest error is recomputed
+                let s = -q - r;
+                let (mut rq, mut rr, rs) = (q.round(), r.round(), s.round());
+                let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+                if dq > dr && dq > ds {
+                    rq = -rr - rs;
+                } else if dr > ds {
+                    rr = -rq - rs;
+                }
+                MapTileHandle {
+                    x: rq as i32,
+                    z: rr as i32,
+                }
+            }
+        }
+    }
+
+    // NOTE: converts column and row of an ascii grid, odd hex rows are shifted half a tile right
+    pub fn offset_to_handle(&self, column: i32, row: i32) -> MapTileHandle {
+        match self {
+            GridKind::Square => MapTileHandle { x: column, z: row },
+            GridKind::Hex => MapTileHandle {
+                x: column - (row - (row & 1)) / 2,
+                z: row,
+            },
+        }
+    }
+
+    pub fn tile_mesh(&self, tile_size: f32, tile_height: f32) -> Mesh {
+        match self {
+            GridKind::Square => {
+                Cuboid::default()
+                    .mesh()
+                    .scaled_by(vec3(tile_size, tile_height, tile_size))
+            }
+            GridKind::Hex => hex_prism_mesh(tile_size, tile_height),
+        }
+    }
+
+    pub fn tile_collider(&self, tile_size: f32, tile_height: f32) -> Collider {
+        match self {
+            GridKind::Square => {
+                Collider::cuboid(tile_size / 2.0, tile_height / 2.0, tile_size / 2.0)
+            }
+            GridKind::Hex => {
+                let half_height = tile_height / 2.0;
+                let points: Vec<Vec3> = hex_corners(tile_size)
+                    .iter()
+                    .flat_map(|c| [vec3(c.x, half_height, c.y), vec3(c.x, -half_height, c.y)])
+                    .collect();
+                Collider::convex_hull(&points).expect("hex prism has a valid convex hull")
+            }
+        }
+    }
+
+    // NOTE: how far the tile reaches from its centre along `dir` on the xz-plane
+    pub fn extent(&self, dir: Vec2, tile_size: f32) -> f32 {
+        match self {
+            GridKind::Square => (dir.x.abs() + dir.y.abs()) * tile_size / 2.0,
+            GridKind::Hex => hex_corners(tile_size)
+                .iter()
+                .map(|corner| corner.dot(dir))
+                .fold(0.0, f32::max),
+        }
+    }
+}
+
+fn hex_corners(tile_size: f32) -> [Vec2; 6] {
+    let radius = tile_size / 3f32.sqrt();
+    std::array::from_fn(|i| Vec2::from_angle(PI / 6.0 + i as f32 * PI / 3.0) * radius)
+}
+
+fn hex_prism_mesh(tile_size: f32, tile_height: f32) -> Mesh {
+    let corners = hex_corners(tile_size);
+    let half_height = tile_height / 2.0;
+    let mut positions: Vec<[f32; 3]> = vec![];
+    let mut normals: Vec<[f32; 3]> = vec![];
+    let mut uvs: Vec<[f32; 2]> = vec![];
+    let mut indices: Vec<u32> = vec![];
+
+    // top and bottom caps as triangle fans
+    for (y, normal) in [(half_height, 1.0), (-half_height, -1.0)] {
+        let center = positions.len() as u32;
+        positions.push([0.0, y, 0.0]);
+        normals.push([0.0, normal, 0.0]);
+        uvs.push([0.5, 0.5]);
+        for corner in corners.iter() {
+            positions.push([corner.x, y, corner.y]);
+            normals.push([0.0, normal, 0.0]);
+            uvs.push([0.5 + corner.x / tile_size, 0.5 + corner.y / tile_size]);
+        }
+        for i in 0..6 {
+            let (a, b) = (center + 1 + i, center + 1 + (i + 1) % 6);
+            if normal > 0.0 {
+                indices.extend([center, b, a]);
+            } else {
+                indices.extend([center, a, b]);
+            }
+        }
+    }
+
+    // one quad per side
+    for i in 0..6 {
+        let (a, b) = (corners[i], corners[(i + 1) % 6]);
+        let normal = ((a + b) / 2.0).normalize();
+        let start = positions.len() as u32;
+        for (corner, y) in [
+            (a, -half_height),
+            (a, half_height),
+            (b, half_height),
+            (b, -half_height),
+        ] {
+            positions.push([corner.x, y, corner.y]);
+            normals.push([normal.x, 0.0, normal.y]);
+        }
+        uvs.extend([[0.0, 1.0], [0.0, 0.0], [1.0, 0.0], [1.0, 1.0]]);
+        indices.extend([start, start + 1, start + 2, start, start + 2, start + 3]);
+    }
+
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+    .with_inserted_indices(Indices::U32(indices))
+}
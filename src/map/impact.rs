@@ -0,0 +1,48 @@
+use bevy::prelude::*;
+
+use super::{MapDescriptor, MapTileHandle, TileEvents, TileSinking, WorldMap};
+
+// NOTE: something hit the ground hard, `handle` is the tile that was hit directly if known
+#[derive(Event, Clone, Copy, Debug)]
+pub struct TileImpact {
+    pub handle: Option<MapTileHandle>,
+    pub position: Vec3,
+    pub damage: f32,
+    pub radius: f32,
+}
+
+// NOTE: the hit tile takes the full damage, the damage falls off towards the edge of the radius
+// the distance is measured to the top of the tiles, so tiles on other levels take less damage
+pub(super) fn apply_tile_impacts(
+    mut impacts: EventReader<TileImpact>,
+    mut map: ResMut<WorldMap>,
+    des: Res<MapDescriptor>,
+    mut events: TileEvents,
+) {
+    for impact in impacts.read() {
+        for (&handle, tile) in map.iter_mut() {
+            if tile.sinking != TileSinking::NotSinking {
+                continue;
+            }
+            let falloff = if impact.handle == Some(handle) {
+                1.0
+            } else if impact.radius <= 0.0 {
+                // NOTE: impacts without a radius only damage the hit tile
+                continue;
+            } else {
+                let top = tile.position + Vec3::Y * des.tile_height / 2.0;
+                let dist = top.distance(impact.position);
+                1.0 - dist / impact.radius
+            };
+            if falloff <= 0.0 {
+                continue;
+            }
+            events.damage(
+                handle,
+                tile,
+                impact.damage * falloff,
+                des.warning_constitution,
+            );
+        }
+    }
+}
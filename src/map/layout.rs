@@ -0,0 +1,168 @@
+use std::{collections::HashMap, fmt};
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    utils::BoxedFuture,
+};
+use serde::Deserialize;
+
+use super::{
+    erosion::ErosionStrategy, platforms::PlatformMotion, terrain::Ramp, MapSelection,
+    MapTileHandle, TileKind,
+};
+
+// NOTE: an arena authored as a `.map.ron` file, either as an ascii grid with a legend or as a tile list
+#[derive(Asset, TypePath, Deserialize, Debug)]
+pub struct MapLayout {
+    pub name: String,
+    #[serde(default)]
+    pub legend: HashMap<char, LayoutTile>,
+    #[serde(default)]
+    pub rows: Vec<String>,
+    #[serde(default)]
+    pub tiles: Vec<PlacedTile>,
+    #[serde(default)]
+    pub erosion: Option<ErosionStrategy>,
+    // NOTE: tile that keeps its island afloat, without one the largest island stays
+    #[serde(default)]
+    pub anchor: Option<(i32, i32)>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct LayoutTile {
+    #[serde(default)]
+    pub height: f32,
+    #[serde(default)]
+    pub kind: TileKind,
+    #[serde(default = "default_constitution")]
+    pub constitution: f32,
+    #[serde(default)]
+    pub ramp: Option<Ramp>,
+    #[serde(default)]
+    pub platform: Option<PlatformMotion>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct PlacedTile {
+    pub x: i32,
+    pub z: i32,
+    #[serde(default)]
+    pub height: f32,
+    #[serde(default)]
+    pub kind: TileKind,
+    #[serde(default = "default_constitution")]
+    pub constitution: f32,
+    #[serde(default)]
+    pub ramp: Option<Ramp>,
+    #[serde(default)]
+    pub platform: Option<PlatformMotion>,
+}
+
+fn default_constitution() -> f32 {
+    1.0
+}
+
+impl MapLayout {
+    // NOTE: grid rows are centred around the origin, rows run along z and columns along x
+    pub fn tiles(&self) -> Vec<(MapTileHandle, LayoutTile)> {
+        let depth = self.rows.len() as i32;
+        let width = self
+            .rows
+            .iter()
+            .map(|row| row.chars().count())
+            .max()
+            .unwrap_or(0) as i32;
+        let mut tiles = Vec::new();
+        for (row, line) in self.rows.iter().enumerate() {
+            for (column, symbol) in line.chars().enumerate() {
+                let Some(tile) = self.legend.get(&symbol) else {
+                    continue;
+                };
+                let handle = MapTileHandle {
+                    x: column as i32 - width / 2,
+                    z: row as i32 - depth / 2,
+                };
+                tiles.push((handle, tile.clone()));
+            }
+        }
+        for placed in self.tiles.iter() {
+            let handle = MapTileHandle {
+                x: placed.x,
+                z: placed.z,
+            };
+            let tile = LayoutTile {
+                height: placed.height,
+                kind: placed.kind,
+                constitution: placed.constitution,
+                ramp: placed.ramp,
+                platform: placed.platform.clone(),
+            };
+            tiles.push((handle, tile));
+        }
+        tiles
+    }
+}
+
+#[derive(Default)]
+pub struct MapLayoutLoader;
+
+#[derive(Debug)]
+pub enum MapLayoutLoaderError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+}
+
+impl fmt::Display for MapLayoutLoaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapLayoutLoaderError::Io(err) => write!(f, "could not read map layout: {}", err),
+            MapLayoutLoaderError::Ron(err) => write!(f, "could not parse map layout: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for MapLayoutLoaderError {}
+
+impl From<std::io::Error> for MapLayoutLoaderError {
+    fn from(err: std::io::Error) -> Self {
+        MapLayoutLoaderError::Io(err)
+    }
+}
+
+impl From<ron::error::SpannedError> for MapLayoutLoaderError {
+    fn from(err: ron::error::SpannedError) -> Self {
+        MapLayoutLoaderError::Ron(err)
+    }
+}
+
+impl AssetLoader for MapLayoutLoader {
+    type Asset = MapLayout;
+    type Settings = ();
+    type Error = MapLayoutLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<MapLayout, MapLayoutLoaderError>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let layout = ron::de::from_bytes::<MapLayout>(&bytes)?;
+            Ok(layout)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["map.ron"]
+    }
+}
+
+pub(super) fn load_map_layouts(
+    mut selection: ResMut<MapSelection>,
+    asset_server: Res<AssetServer>,
+) {
+    selection.folder = asset_server.load_folder("maps");
+}
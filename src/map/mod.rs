@@ -1,31 +1,189 @@
+use std::time::Duration;
+
 use bevy::{
-    math::vec3,
+    asset::LoadedFolder,
+    ecs::system::SystemParam,
     prelude::*,
     utils::{hashbrown::HashMap, HashSet},
 };
 use bevy_rapier3d::{
-    dynamics::CoefficientCombineRule,
-    geometry::{Collider, Friction},
+    dynamics::{CoefficientCombineRule, RigidBody},
+    geometry::{Friction, Restitution},
 };
 
-use rand::seq::IteratorRandom;
+use serde::Deserialize;
+
+pub mod chunks;
+pub mod connectivity;
+pub mod erosion;
+pub mod generator;
+pub mod grid;
+pub mod impact;
+pub mod layout;
+pub mod navigation;
+pub mod platforms;
+pub mod regrowth;
+pub mod sinking;
+pub mod terrain;
+pub mod visuals;
+
+use crate::key_bindings::LocalPlayers;
+use chunks::{mark_damaged_chunks, rebuild_chunks, split_warned_tiles, MapEntities};
+use connectivity::{reset_standing_tiles, sink_severed_fragments, FragmentSevered, StandingTiles};
+use erosion::{
+    reduce_leaf_constitution, reset_erosion_clock, ErosionClock, ErosionFocus, ErosionStrategy,
+};
+use generator::{generate_island, GeneratorParams};
+use grid::GridKind;
+use impact::{apply_tile_impacts, TileImpact};
+use layout::{load_map_layouts, LayoutTile, MapLayout, MapLayoutLoader};
+use navigation::{build_nav_graph, clear_nav_graph, update_nav_graph, NavGraph};
+use platforms::{move_platforms, PlatformMotion};
+use regrowth::{
+    clear_regrowth, emerge_tiles, queue_regrowth, raise_tiles, tick_regrowth, RaiseTile,
+    RegrowQueue,
+};
+use sinking::{sink_tile, start_sinking_bodies};
+use terrain::{Ramp, TileAssets, TileShape};
+use visuals::{
+    setup_tile_visuals, spawn_sinking_dust, update_dust, update_tile_materials, wobble_tiles,
+    TileMaterials, TileVisual,
+};
 
 #[derive(Resource, Default, DerefMut, Deref)]
 pub struct WorldMap(HashMap<MapTileHandle, MapTile>);
 
 pub struct MapTile {
     pub position: Vec3,
+    // NOTE: height of the tile above the sea level ground, negative for pits
+    pub elevation: f32,
+    pub ramp: Option<Ramp>,
+    // NOTE: platforms move away from their grid position
+    pub platform: Option<PlatformMotion>,
     pub constitution: f32,
+    pub max_constitution: f32,
     pub sinking: TileSinking,
+    pub kind: TileKind,
+    pub crumble: Option<Timer>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Deserialize)]
+pub enum TileKind {
+    #[default]
+    Grass,
+    Sand,
+    Rock,
+    Ice,
+    Bouncy,
+    Mud,
+    Crumbling,
 }
 
+impl TileKind {
+    pub const ALL: [TileKind; 7] = [
+        TileKind::Grass,
+        TileKind::Sand,
+        TileKind::Rock,
+        TileKind::Ice,
+        TileKind::Bouncy,
+        TileKind::Mud,
+        TileKind::Crumbling,
+    ];
+
+    pub fn color(&self) -> Color {
+        match self {
+            TileKind::Grass => Color::GREEN,
+            TileKind::Sand => Color::rgb(0.9, 0.8, 0.5),
+            TileKind::Rock => Color::GRAY,
+            TileKind::Ice => Color::rgb(0.75, 0.9, 1.0),
+            TileKind::Bouncy => Color::rgb(1.0, 0.3, 0.7),
+            TileKind::Mud => Color::rgb(0.4, 0.25, 0.1),
+            TileKind::Crumbling => Color::rgb(0.6, 0.45, 0.3),
+        }
+    }
+
+    pub fn material(&self) -> StandardMaterial {
+        let perceptual_roughness = match self {
+            TileKind::Ice => 0.1,
+            TileKind::Bouncy => 0.4,
+            _ => 0.9,
+        };
+        StandardMaterial {
+            base_color: self.color(),
+            perceptual_roughness,
+            ..default()
+        }
+    }
+
+    // NOTE: ice uses the min rule so the player's own friction can't give it grip back
+    pub fn friction(&self) -> Friction {
+        match self {
+            TileKind::Ice => Friction {
+                coefficient: 0.02,
+                combine_rule: CoefficientCombineRule::Min,
+            },
+            TileKind::Mud => Friction {
+                coefficient: 1.5,
+                combine_rule: CoefficientCombineRule::Max,
+            },
+            TileKind::Sand => Friction {
+                coefficient: 0.7,
+                combine_rule: CoefficientCombineRule::Average,
+            },
+            _ => Friction {
+                coefficient: 0.9,
+                combine_rule: CoefficientCombineRule::Average,
+            },
+        }
+    }
+
+    pub fn restitution(&self) -> Restitution {
+        match self {
+            // NOTE: above 1.0 every bounce gains height, players can not steer off the tile in the air
+            TileKind::Bouncy => Restitution {
+                coefficient: 0.9,
+                combine_rule: CoefficientCombineRule::Max,
+            },
+            _ => Restitution::default(),
+        }
+    }
+
+    // NOTE: multiplier on the constitution reduction rate of the map
+    pub fn decay_rate(&self) -> f32 {
+        match self {
+            TileKind::Rock => 0.5,
+            TileKind::Sand => 1.5,
+            TileKind::Crumbling => 2.0,
+            _ => 1.0,
+        }
+    }
+
+    pub fn move_multiplier(&self) -> f32 {
+        match self {
+            TileKind::Mud => 0.4,
+            _ => 1.0,
+        }
+    }
+
+    pub fn linear_damping(&self) -> f32 {
+        match self {
+            TileKind::Mud => 4.0,
+            _ => 0.0,
+        }
+    }
+}
+
+// NOTE: the tile an entity is standing on, updated by the grounded check of the players
+#[derive(Component, Default, Clone, Copy, Debug)]
+pub struct GroundTile(pub Option<MapTileHandle>);
+
 #[derive(Resource, Default, Deref, DerefMut)]
 pub struct LeafTiles(HashSet<MapTileHandle>);
 
 #[derive(Component, Clone, Copy, Debug, Hash, PartialEq, Eq)]
 pub struct MapTileHandle {
-    x: i32,
-    z: i32,
+    pub x: i32,
+    pub z: i32,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -35,12 +193,145 @@ pub enum TileSinking {
     Sunk,
 }
 
+#[derive(Event, Clone, Copy, Debug)]
+pub struct TileDamaged {
+    pub handle: MapTileHandle,
+    // NOTE: not read inside the game yet, kept for effects reacting to the event
+    #[allow(dead_code)]
+    pub position: Vec3,
+    #[allow(dead_code)]
+    pub damage: f32,
+}
+
+// NOTE: sent once when a tile is about to sink, either low on constitution or a stepped on crumbling tile
+#[derive(Event, Clone, Copy, Debug)]
+pub struct TileWarning {
+    pub handle: MapTileHandle,
+    #[allow(dead_code)]
+    pub position: Vec3,
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct TileStartedSinking {
+    pub handle: MapTileHandle,
+    pub position: Vec3,
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct TileSunk {
+    pub handle: MapTileHandle,
+    pub position: Vec3,
+}
+
+// NOTE: changes the state of map tiles and announces every change with an event
+#[derive(SystemParam)]
+pub struct TileEvents<'w> {
+    damaged: EventWriter<'w, TileDamaged>,
+    warning: EventWriter<'w, TileWarning>,
+    started_sinking: EventWriter<'w, TileStartedSinking>,
+    sunk: EventWriter<'w, TileSunk>,
+}
+
+impl<'w> TileEvents<'w> {
+    pub fn damage(
+        &mut self,
+        handle: MapTileHandle,
+        tile: &mut MapTile,
+        damage: f32,
+        warning_constitution: f32,
+    ) {
+        let was_warned = tile.constitution < warning_constitution;
+        tile.constitution -= damage;
+        let position = tile.position;
+        self.damaged.send(TileDamaged {
+            handle,
+            position,
+            damage,
+        });
+        if !was_warned && tile.constitution < warning_constitution {
+            self.warn(handle, tile);
+        }
+    }
+
+    pub fn warn(&mut self, handle: MapTileHandle, tile: &MapTile) {
+        self.warning.send(TileWarning {
+            handle,
+            position: tile.position,
+        });
+    }
+
+    pub fn start_sinking(&mut self, handle: MapTileHandle, tile: &mut MapTile) {
+        tile.sinking = TileSinking::Sinking;
+        self.started_sinking.send(TileStartedSinking {
+            handle,
+            position: tile.position,
+        });
+    }
+
+    pub fn sunk(&mut self, handle: MapTileHandle, tile: &mut MapTile) {
+        tile.sinking = TileSinking::Sunk;
+        self.sunk.send(TileSunk {
+            handle,
+            position: tile.position,
+        });
+    }
+}
+
 #[derive(Resource)]
 pub struct MapDescriptor {
     tile_size: f32,
     tile_height: f32,
-    radius: u32,
+    pub radius: u32,
     constitution_reduction_rate: f32,
+    warning_constitution: f32,
+    crumble_delay: Duration,
+    sink_acceleration: f32,
+    sink_tilt_speed: f32,
+    sink_max_tilt: f32,
+    emerge_speed: f32,
+    // NOTE: sunk tiles re-emerge after this delay, `None` keeps them sunk
+    pub regrowth: Option<Duration>,
+    // NOTE: players spend this many coins to raise a tile, `None` disables raising and coins
+    pub raise_cost: Option<u32>,
+    pub generator: GeneratorParams,
+    pub grid: GridKind,
+    pub erosion: ErosionStrategy,
+    anchor: Option<MapTileHandle>,
+    // NOTE: static tiles are merged into chunks, tiles get their own entity once they move
+    pub chunked: bool,
+}
+
+impl MapDescriptor {
+    pub fn tile_position(&self, handle: MapTileHandle) -> Vec3 {
+        self.grid.position(handle, self.tile_size)
+    }
+
+    pub fn tile_size(&self) -> f32 {
+        self.tile_size
+    }
+
+    // NOTE: 0 for a safe tile up to 1 right before the tile starts sinking
+    pub fn tile_warning(&self, tile: &MapTile) -> f32 {
+        tile.warning(self.warning_constitution)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MapChoice {
+    #[default]
+    Circle,
+    Procedural,
+    Layout(AssetId<MapLayout>),
+}
+
+// NOTE: layouts are loaded from `assets/maps`, the choice is applied when the next map is created
+#[derive(Resource, Default)]
+pub struct MapSelection {
+    pub folder: Handle<LoadedFolder>,
+    pub selected: MapChoice,
+    pub grid: GridKind,
+    // NOTE: `None` uses the erosion of the map layout
+    pub erosion: Option<ErosionStrategy>,
 }
 
 impl Default for MapDescriptor {
@@ -50,6 +341,19 @@ impl Default for MapDescriptor {
             tile_height: 2.0,
             radius: 8,
             constitution_reduction_rate: 3.0,
+            warning_constitution: 0.3,
+            crumble_delay: Duration::from_millis(1000),
+            sink_acceleration: 3.0,
+            sink_tilt_speed: 0.4,
+            sink_max_tilt: 0.5,
+            emerge_speed: 1.5,
+            regrowth: None,
+            raise_cost: None,
+            generator: GeneratorParams::default(),
+            grid: GridKind::Square,
+            erosion: ErosionStrategy::Random,
+            anchor: None,
+            chunked: true,
         }
     }
 }
@@ -69,23 +373,73 @@ impl Plugin for MapPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<WorldMap>()
             .init_resource::<LeafTiles>()
+            .init_resource::<MapSelection>()
+            .init_resource::<ErosionFocus>()
+            .init_resource::<ErosionClock>()
+            .init_resource::<StandingTiles>()
+            .add_event::<FragmentSevered>()
+            .add_event::<TileDamaged>()
+            .add_event::<TileWarning>()
+            .add_event::<TileStartedSinking>()
+            .add_event::<TileSunk>()
+            .add_event::<TileImpact>()
+            .add_event::<RaiseTile>()
+            .init_resource::<RegrowQueue>()
+            .init_resource::<MapEntities>()
+            .init_resource::<NavGraph>()
             .init_state::<MapState>()
             .insert_resource(MapDescriptor::default())
+            .init_asset::<MapLayout>()
+            .init_asset_loader::<MapLayoutLoader>()
+            .add_systems(Startup, (load_map_layouts, setup_tile_visuals))
             .add_systems(
                 OnEnter(MapState::CreateMap),
                 (create_map, calc_leaf_tiles.after(create_map)),
             )
             .add_systems(OnEnter(MapState::SpawnMap), spawn_map)
-            .add_systems(OnEnter(MapState::DespawnMap), despawn_map)
+            .add_systems(
+                OnEnter(MapState::GamePlay),
+                (reset_erosion_clock, reset_standing_tiles, build_nav_graph),
+            )
+            .add_systems(
+                OnEnter(MapState::DespawnMap),
+                (despawn_map, clear_regrowth, clear_nav_graph),
+            )
             .add_systems(
                 Update,
                 (
                     reduce_leaf_constitution,
+                    apply_tile_impacts,
                     update_leaf_tiles,
-                    check_leaf_constitution,
-                    sink_tile,
+                    check_leaf_constitution.after(apply_tile_impacts),
+                    crumble_tiles,
+                    sink_severed_fragments
+                        .after(check_leaf_constitution)
+                        .after(crumble_tiles),
+                    start_sinking_bodies.after(sink_severed_fragments),
+                    sink_tile.after(start_sinking_bodies),
+                    queue_regrowth.after(sink_tile),
+                    tick_regrowth,
+                    raise_tiles.after(tick_regrowth),
+                    emerge_tiles,
+                    split_warned_tiles,
+                    move_platforms.before(start_sinking_bodies),
+                    update_nav_graph
+                        .after(sink_severed_fragments)
+                        .after(raise_tiles),
                 )
                     .run_if(in_state(MapState::GamePlay)),
+            )
+            .add_systems(
+                Update,
+                (
+                    update_tile_materials,
+                    wobble_tiles,
+                    spawn_sinking_dust,
+                    update_dust,
+                    mark_damaged_chunks,
+                    rebuild_chunks.after(mark_damaged_chunks),
+                ),
             );
     }
 }
@@ -93,154 +447,250 @@ impl Plugin for MapPlugin {
 fn create_map(
     mut map: ResMut<WorldMap>,
     mut state: ResMut<NextState<MapState>>,
-    des: Res<MapDescriptor>,
+    mut des: ResMut<MapDescriptor>,
+    selection: Res<MapSelection>,
+    layouts: Res<Assets<MapLayout>>,
+    local_players: Res<LocalPlayers>,
 ) {
+    des.grid = selection.grid;
+    des.erosion = selection.erosion.clone().unwrap_or_default();
+    // NOTE: the island centre stays the main island, layouts may choose their own anchor
+    des.anchor = Some(MapTileHandle { x: 0, z: 0 });
+    match selection.selected {
+        MapChoice::Circle => create_circle_map(&mut map, &des),
+        MapChoice::Procedural => {
+            info!("Generating island with seed {}.", des.generator.seed);
+            // NOTE: the practice box spawns next to the players and needs room as well
+            let tiles = generate_island(&des.generator, des.radius, local_players.num + 1);
+            insert_tiles(&mut map, &des, tiles);
+        }
+        MapChoice::Layout(id) => match layouts.get(id) {
+            Some(layout) => {
+                info!("Creating map {:?}.", layout.name);
+                if selection.erosion.is_none() {
+                    des.erosion = layout.erosion.clone().unwrap_or_default();
+                }
+                des.anchor = layout.anchor.map(|(x, z)| des.grid.offset_to_handle(x, z));
+                insert_tiles(&mut map, &des, layout.tiles());
+            }
+            None => {
+                warn!(
+                    "Map layout {:?} is not loaded, creating the default map!",
+                    id
+                );
+                create_circle_map(&mut map, &des);
+            }
+        },
+    }
+    state.set(MapState::SpawnMap);
+}
+
+fn create_circle_map(map: &mut WorldMap, des: &MapDescriptor) {
     let radius = des.radius as i32;
-    for x in (-radius)..radius {
-        for z in (-radius)..radius {
-            if x * x + z * z >= radius * radius {
+    let max_dist = des.radius as f32 * des.tile_size;
+    // NOTE: axial hex coordinates are skewed, so search a wider range than the radius
+    for x in (-2 * radius)..=(2 * radius) {
+        for z in (-2 * radius)..=(2 * radius) {
+            let handle = MapTileHandle { x, z };
+            let position = des.tile_position(handle);
+            if position.length() >= max_dist {
                 continue;
             }
-            let xpos = x as f32 * des.tile_size;
-            let zpos = z as f32 * des.tile_size;
             let tile = MapTile {
-                position: vec3(xpos, 0.0, zpos),
+                position,
+                elevation: 0.0,
+                ramp: None,
+                platform: None,
                 constitution: 1.0,
+                max_constitution: 1.0,
                 sinking: TileSinking::NotSinking,
+                kind: TileKind::Grass,
+                crumble: None,
             };
-            let handle = MapTileHandle { x, z };
             map.0.insert(handle, tile);
-            state.set(MapState::SpawnMap);
         }
     }
 }
 
-fn calc_leaf_tiles(map: Res<WorldMap>, mut leafs: ResMut<LeafTiles>) {
-    for &MapTileHandle { x, z } in map.keys() {
-        let nx = MapTileHandle { x: x - 1, z };
-        let px = MapTileHandle { x: x + 1, z };
-        let nz = MapTileHandle { x, z: z - 1 };
-        let pz = MapTileHandle { x, z: z + 1 };
-        let contains_all = map.contains_key(&nx)
-            && map.contains_key(&px)
-            && map.contains_key(&nz)
-            && map.contains_key(&pz);
-        if contains_all {
-            continue;
+// NOTE: generated and authored tiles use grid offset coordinates
+fn insert_tiles(
+    map: &mut WorldMap,
+    des: &MapDescriptor,
+    tiles: impl IntoIterator<Item = (MapTileHandle, LayoutTile)>,
+) {
+    for (offset, layout_tile) in tiles {
+        let handle = des.grid.offset_to_handle(offset.x, offset.z);
+        let tile = MapTile {
+            position: des.tile_position(handle) + Vec3::Y * layout_tile.height,
+            elevation: layout_tile.height,
+            ramp: layout_tile.ramp,
+            platform: layout_tile.platform,
+            constitution: layout_tile.constitution,
+            max_constitution: layout_tile.constitution,
+            sinking: TileSinking::NotSinking,
+            kind: layout_tile.kind,
+            crumble: None,
+        };
+        map.0.insert(handle, tile);
+    }
+}
+
+// NOTE: a standing tile is a leaf if any of its neighbours is missing or not standing land
+// platforms are always leafs, they erode like the coast
+fn is_leaf(map: &WorldMap, grid: GridKind, handle: MapTileHandle) -> bool {
+    if map.get(&handle).is_some_and(|tile| tile.platform.is_some()) {
+        return true;
+    }
+    grid.neighbours(handle)
+        .iter()
+        .any(|n| !map.get(n).is_some_and(MapTile::is_land))
+}
+
+// NOTE: re-evaluates the leaf state of a tile and its neighbours after the tile changed
+fn refresh_leafs(leafs: &mut LeafTiles, map: &WorldMap, grid: GridKind, handle: MapTileHandle) {
+    for h in grid.neighbours(handle).into_iter().chain([handle]) {
+        let standing = map
+            .get(&h)
+            .is_some_and(|tile| tile.sinking == TileSinking::NotSinking);
+        if standing && is_leaf(map, grid, h) {
+            leafs.insert(h);
+        } else {
+            leafs.remove(&h);
         }
-        leafs.insert(MapTileHandle { x, z });
     }
 }
 
+fn calc_leaf_tiles(map: Res<WorldMap>, mut leafs: ResMut<LeafTiles>, des: Res<MapDescriptor>) {
+    for &handle in map.keys() {
+        if is_leaf(&map, des.grid, handle) {
+            leafs.insert(handle);
+        }
+    }
+}
+
+fn spawn_tile(
+    cmd: &mut Commands,
+    shape: &TileShape,
+    tile_materials: &TileMaterials,
+    handle: MapTileHandle,
+    tile: &MapTile,
+) -> Entity {
+    cmd.spawn(SpatialBundle::from_transform(Transform::from_translation(
+        tile.position,
+    )))
+    .insert(handle)
+    .insert(RigidBody::Fixed)
+    .insert(shape.collider.clone())
+    .insert(tile.kind.friction())
+    .insert(tile.kind.restitution())
+    .with_children(|parent| {
+        parent.spawn((
+            PbrBundle {
+                mesh: shape.mesh.clone(),
+                material: tile_materials.get(tile.kind, tile.damage_stage()),
+                ..default()
+            },
+            TileVisual(handle),
+        ));
+    })
+    .id()
+}
+
 fn spawn_map(
     mut cmd: Commands,
     map: Res<WorldMap>,
     mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
+    tile_materials: Res<TileMaterials>,
     des: Res<MapDescriptor>,
+    mut entities: ResMut<MapEntities>,
     mut state: ResMut<NextState<MapState>>,
 ) {
-    let mesh = meshes.add(Cuboid::default().mesh().scaled_by(vec3(
-        des.tile_size,
-        des.tile_height,
-        des.tile_size,
-    )));
-    for (handle, tile) in map.iter() {
-        let material = materials.add(Color::GREEN);
-        cmd.spawn(PbrBundle {
-            mesh: mesh.clone(),
-            material: material.clone(),
-            transform: Transform::from_translation(tile.position),
-            ..default()
-        })
-        .insert(*handle)
-        .insert(Collider::cuboid(
-            des.tile_size / 2.0,
-            des.tile_height / 2.0,
-            des.tile_size / 2.0,
-        ))
-        .insert(Friction {
-            coefficient: 0.9,
-            combine_rule: CoefficientCombineRule::Average,
-        });
+    let mut assets = TileAssets::new(&des);
+    for (&handle, tile) in map.iter() {
+        let shape = assets.insert(tile, &mut meshes);
+        if tile.platform.is_some() {
+            let entity = spawn_tile(&mut cmd, shape, &tile_materials, handle, tile);
+            cmd.entity(entity)
+                .insert(RigidBody::KinematicPositionBased)
+                .insert(des.platform(handle, tile));
+            entities.insert_tile(handle, entity);
+        } else if des.chunked {
+            entities.add_to_chunk(&mut cmd, handle, tile);
+        } else {
+            let entity = spawn_tile(&mut cmd, shape, &tile_materials, handle, tile);
+            entities.insert_tile(handle, entity);
+        }
     }
+    cmd.insert_resource(assets);
     state.set(MapState::GamePlay);
 }
 
-fn despawn_map() {}
+fn despawn_map(
+    mut cmd: Commands,
+    mut entities: ResMut<MapEntities>,
+    mut map: ResMut<WorldMap>,
+    mut leafs: ResMut<LeafTiles>,
+    mut state: ResMut<NextState<MapState>>,
+) {
+    entities.despawn_all(&mut cmd);
+    map.clear();
+    leafs.clear();
+    state.set(MapState::CreateMap);
+}
 
-// NOTE: select random leaf and reduce its constitution
-fn reduce_leaf_constitution(
+// NOTE: crumbling tiles start sinking a short while after someone stepped on them
+fn crumble_tiles(
+    query: Query<&GroundTile>,
     mut map: ResMut<WorldMap>,
-    leafs: Res<LeafTiles>,
     time: Res<Time>,
     des: Res<MapDescriptor>,
+    mut events: TileEvents,
 ) {
-    let Some(random_handle) = leafs.iter().choose(&mut rand::thread_rng()) else {
-        error!("Unable to find Random Leaf in leafs: {:?}!", leafs.clone());
-        return;
-    };
-    let Some(random_leaf) = map.get_mut(random_handle) else {
-        error!("Unable to find a Leaf in Map!");
-        return;
-    };
-    random_leaf.constitution -= des.constitution_reduction_rate * time.delta_seconds();
-}
-
-// NOTE: checks if leaf node has critical constitution, then randomly checks if it
-fn check_leaf_constitution(mut query: Query<&MapTileHandle>, mut map: ResMut<WorldMap>) {
-    for handle in query.iter_mut() {
-        let Some(tile) = map.get_mut(handle) else {
+    for ground in query.iter() {
+        let Some(handle) = ground.0 else {
+            continue;
+        };
+        let Some(tile) = map.get_mut(&handle) else {
             continue;
         };
+        if tile.kind == TileKind::Crumbling && tile.crumble.is_none() {
+            tile.crumble = Some(Timer::new(des.crumble_delay, TimerMode::Once));
+            events.warn(handle, tile);
+        }
+    }
+    for (&handle, tile) in map.iter_mut() {
         if tile.sinking != TileSinking::NotSinking {
             continue;
         }
-        if tile.constitution >= 0.0 {
+        let Some(timer) = tile.crumble.as_mut() else {
             continue;
+        };
+        if timer.tick(time.delta()).finished() {
+            events.start_sinking(handle, tile);
         }
-        tile.sinking = TileSinking::Sinking;
     }
 }
 
-// NOTE: Handle the logic for sinking a tile
-fn sink_tile(
-    mut query: Query<(&MapTileHandle, &mut Transform)>,
-    mut map: ResMut<WorldMap>,
-    time: Res<Time>,
-) {
-    for (handle, mut trans) in query.iter_mut() {
-        let Some(tile) = map.get_mut(handle) else {
-            continue;
-        };
-        if TileSinking::Sinking != tile.sinking {
+// NOTE: checks if leaf node has critical constitution, then randomly checks if it
+fn check_leaf_constitution(mut map: ResMut<WorldMap>, mut events: TileEvents) {
+    for (&handle, tile) in map.iter_mut() {
+        if tile.sinking != TileSinking::NotSinking {
             continue;
         }
-        if trans.translation.y < -3.0 {
-            tile.sinking = TileSinking::Sunk;
+        if tile.constitution >= 0.0 {
+            continue;
         }
-        trans.translation.y -= time.delta_seconds();
+        events.start_sinking(handle, tile);
     }
 }
 
-fn update_leaf_tiles(mut leafs: ResMut<LeafTiles>, map: Res<WorldMap>) {
-    for &MapTileHandle { x, z } in map.keys() {
-        let Some(tile) = map.get(&MapTileHandle { x, z }) else {
-            continue;
-        };
-
+fn update_leaf_tiles(mut leafs: ResMut<LeafTiles>, map: Res<WorldMap>, des: Res<MapDescriptor>) {
+    for (&handle, tile) in map.iter() {
         if tile.sinking != TileSinking::Sunk {
             continue;
         }
 
-        let neighs = [
-            MapTileHandle { x: x - 1, z },
-            MapTileHandle { x: x + 1, z },
-            MapTileHandle { x, z: z - 1 },
-            MapTileHandle { x, z: z + 1 },
-        ];
-
-        for n in neighs.iter() {
+        for n in des.grid.neighbours(handle).iter() {
             let Some(tile) = map.get(n) else {
                 continue;
             };
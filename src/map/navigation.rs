@@ -0,0 +1,270 @@
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, VecDeque},
+};
+
+use bevy::{prelude::*, utils::HashMap};
+
+use super::{
+    grid::GridKind, regrowth::RaiseTile, MapDescriptor, MapTileHandle, TileStartedSinking, WorldMap,
+};
+
+// NOTE: highest step up between two tiles an agent can still walk, higher tiles need a ramp
+const MAX_STEP: f32 = 0.5;
+
+pub struct NavNode {
+    // centre of the walkable top of the tile
+    pub position: Vec3,
+    // number of tiles to the closest tile at the coast, 0 for the coast itself
+    pub edge_distance: u32,
+    links: Vec<(MapTileHandle, f32)>,
+}
+
+impl NavNode {
+    // NOTE: neighbours reachable from this tile with the cost of getting there
+    pub fn links(&self) -> &[(MapTileHandle, f32)] {
+        &self.links
+    }
+}
+
+// NOTE: walkable land of the current map, rebuilt whenever tiles start sinking or are raised
+// platforms are left out, they do not stay where the graph would expect them
+#[derive(Resource, Default)]
+pub struct NavGraph {
+    nodes: HashMap<MapTileHandle, NavNode>,
+    grid: GridKind,
+    tile_size: f32,
+}
+
+#[derive(PartialEq)]
+struct Candidate {
+    estimate: f32,
+    handle: MapTileHandle,
+}
+
+impl Eq for Candidate {}
+
+impl Ord for Candidate {
+    // reversed, so the binary heap pops the lowest estimate first
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.estimate.total_cmp(&self.estimate)
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl NavGraph {
+    pub fn build(map: &WorldMap, des: &MapDescriptor) -> Self {
+        let walkable = |handle: &MapTileHandle| map.get(handle).filter(|tile| tile.is_land());
+        let mut nodes = HashMap::new();
+        for (&handle, tile) in map.iter().filter(|(_, tile)| tile.is_land()) {
+            let links = des
+                .grid
+                .neighbours(handle)
+                .into_iter()
+                .filter_map(|n| Some((n, walkable(&n)?)))
+                .filter_map(|(n, other)| {
+                    // heights are compared where the tiles meet, so ramps connect the levels
+                    let border = (tile.position + other.position) / 2.0;
+                    let climb =
+                        des.surface_height(other, border) - des.surface_height(tile, border);
+                    let cost = tile.position.xz().distance(other.position.xz()) + climb.max(0.0);
+                    (climb <= MAX_STEP).then_some((n, cost))
+                })
+                .collect();
+            let top = des.surface_height(tile, tile.position);
+            let node = NavNode {
+                position: Vec3::new(tile.position.x, top, tile.position.z),
+                edge_distance: u32::MAX,
+                links,
+            };
+            nodes.insert(handle, node);
+        }
+
+        // breadth first from the coast inwards
+        let mut queue = VecDeque::new();
+        for (&handle, node) in nodes.iter_mut() {
+            if des
+                .grid
+                .neighbours(handle)
+                .iter()
+                .any(|n| walkable(n).is_none())
+            {
+                node.edge_distance = 0;
+                queue.push_back(handle);
+            }
+        }
+        while let Some(handle) = queue.pop_front() {
+            let next = nodes[&handle].edge_distance + 1;
+            for n in des.grid.neighbours(handle) {
+                let Some(node) = nodes.get_mut(&n) else {
+                    continue;
+                };
+                if node.edge_distance > next {
+                    node.edge_distance = next;
+                    queue.push_back(n);
+                }
+            }
+        }
+
+        Self {
+            nodes,
+            grid: des.grid,
+            tile_size: des.tile_size,
+        }
+    }
+
+    pub fn node(&self, handle: MapTileHandle) -> Option<&NavNode> {
+        self.nodes.get(&handle)
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = (&MapTileHandle, &NavNode)> {
+        self.nodes.iter()
+    }
+
+    // NOTE: the walkable tile at `pos`, falls back to the closest neighbour next to the coast
+    pub fn handle_at(&self, pos: Vec3) -> Option<MapTileHandle> {
+        let handle = self.grid.handle_at(pos, self.tile_size);
+        std::iter::once(handle)
+            .chain(self.grid.neighbours(handle))
+            .filter_map(|h| Some((h, self.nodes.get(&h)?)))
+            .min_by(|(_, a), (_, b)| {
+                let a = a.position.xz().distance(pos.xz());
+                let b = b.position.xz().distance(pos.xz());
+                a.total_cmp(&b)
+            })
+            .map(|(h, _)| h)
+    }
+
+    // NOTE: A* over the walkable tiles, the path includes both ends
+    pub fn find_path(&self, from: MapTileHandle, to: MapTileHandle) -> Option<Vec<MapTileHandle>> {
+        let goal = self.nodes.get(&to)?.position;
+        let heuristic =
+            |handle: MapTileHandle| self.nodes[&handle].position.xz().distance(goal.xz());
+        self.nodes.get(&from)?;
+
+        let mut costs = HashMap::from([(from, 0.0)]);
+        let mut came_from: HashMap<MapTileHandle, MapTileHandle> = HashMap::new();
+        let mut open = BinaryHeap::from([Candidate {
+            estimate: heuristic(from),
+            handle: from,
+        }]);
+        while let Some(Candidate { handle, .. }) = open.pop() {
+            if handle == to {
+                let mut path = vec![to];
+                while let Some(prev) = came_from.get(path.last()?) {
+                    path.push(*prev);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            let cost = costs[&handle];
+            for &(n, step) in self.nodes[&handle].links.iter() {
+                let next = cost + step;
+                if costs.get(&n).is_some_and(|known| *known <= next) {
+                    continue;
+                }
+                costs.insert(n, next);
+                came_from.insert(n, handle);
+                open.push(Candidate {
+                    estimate: next + heuristic(n),
+                    handle: n,
+                });
+            }
+        }
+        None
+    }
+
+    // NOTE: waypoints on the walkable tops between two world positions
+    pub fn path(&self, from: Vec3, to: Vec3) -> Option<Vec<Vec3>> {
+        let path = self.find_path(self.handle_at(from)?, self.handle_at(to)?)?;
+        Some(path.iter().map(|h| self.nodes[h].position).collect())
+    }
+
+    // NOTE: distance on the xz-plane from `pos` to the water at the closest coast tile
+    pub fn distance_to_edge(&self, pos: Vec3) -> Option<f32> {
+        self.nodes
+            .values()
+            .filter(|node| node.edge_distance == 0)
+            .map(|node| node.position.xz().distance(pos.xz()) + self.tile_size / 2.0)
+            .reduce(f32::min)
+    }
+
+    // NOTE: the tile furthest from the coast, the closest to `near` among equally safe tiles
+    pub fn safest_tile(&self, near: Vec3) -> Option<MapTileHandle> {
+        self.nodes
+            .iter()
+            .max_by(|(_, a), (_, b)| {
+                a.edge_distance.cmp(&b.edge_distance).then_with(|| {
+                    let a = a.position.xz().distance(near.xz());
+                    let b = b.position.xz().distance(near.xz());
+                    b.total_cmp(&a)
+                })
+            })
+            .map(|(handle, _)| *handle)
+    }
+
+    // NOTE: `count` tops of inland tiles spread over the island, the safest tile closest to the
+    // centre comes first
+    pub fn spawn_points(&self, count: usize) -> Vec<Vec3> {
+        let deepest = self.nodes.values().map(|node| node.edge_distance).max();
+        let Some(deepest) = deepest else {
+            return vec![];
+        };
+        // keep away from the coast where the island is large enough
+        let min_edge_distance = deepest.min(1);
+        let candidates: Vec<&NavNode> = self
+            .nodes
+            .values()
+            .filter(|node| node.edge_distance >= min_edge_distance)
+            .collect();
+        let mut points: Vec<Vec3> = vec![];
+        while points.len() < count {
+            // the candidate furthest from all points taken so far, the safest among equals
+            let next = candidates.iter().max_by(|a, b| {
+                let spread = |node: &NavNode| {
+                    points
+                        .iter()
+                        .map(|point| point.xz().distance(node.position.xz()))
+                        .reduce(f32::min)
+                        .unwrap_or(0.0)
+                };
+                let centre = |node: &NavNode| node.position.xz().length();
+                spread(a)
+                    .total_cmp(&spread(b))
+                    .then(a.edge_distance.cmp(&b.edge_distance))
+                    .then(centre(b).total_cmp(&centre(a)))
+            });
+            match next {
+                Some(node) => points.push(node.position),
+                None => break,
+            }
+        }
+        points
+    }
+}
+
+pub fn build_nav_graph(mut graph: ResMut<NavGraph>, map: Res<WorldMap>, des: Res<MapDescriptor>) {
+    *graph = NavGraph::build(&map, &des);
+}
+
+pub(super) fn update_nav_graph(
+    mut graph: ResMut<NavGraph>,
+    mut started_events: EventReader<TileStartedSinking>,
+    mut raise_events: EventReader<RaiseTile>,
+    map: Res<WorldMap>,
+    des: Res<MapDescriptor>,
+) {
+    let changed = started_events.read().count() + raise_events.read().count() > 0;
+    if changed {
+        *graph = NavGraph::build(&map, &des);
+    }
+}
+
+pub(super) fn clear_nav_graph(mut graph: ResMut<NavGraph>) {
+    *graph = NavGraph::default();
+}
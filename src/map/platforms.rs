@@ -0,0 +1,119 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
+
+use super::{sinking::SinkingMotion, MapDescriptor, MapTile, MapTileHandle, TileSinking, WorldMap};
+
+// NOTE: tiles that leave their grid position, moved as kinematic bodies so they carry the players
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub enum PlatformMotion {
+    // moves back and forth along grid offsets from its own tile, `speed` in tiles per second
+    Path { points: Vec<(i32, i32)>, speed: f32 },
+    // spins around its centre, `speed` in degrees per second
+    Turntable { speed: f32 },
+    // drifts on the water and turns away from land, `speed` in tiles per second
+    Raft { speed: f32 },
+}
+
+#[derive(Component)]
+pub(super) struct Platform {
+    waypoints: Vec<Vec3>,
+    leg: usize,
+    forward: bool,
+    heading: Vec2,
+}
+
+impl MapDescriptor {
+    pub(super) fn platform(&self, handle: MapTileHandle, tile: &MapTile) -> Platform {
+        let mut waypoints = vec![tile.position];
+        if let Some(PlatformMotion::Path { points, .. }) = &tile.platform {
+            waypoints.extend(points.iter().map(|&(dx, dz)| {
+                let offset = MapTileHandle {
+                    x: handle.x + dx,
+                    z: handle.z + dz,
+                };
+                self.tile_position(offset) + Vec3::Y * tile.elevation
+            }));
+        }
+        Platform {
+            waypoints,
+            leg: 0,
+            forward: true,
+            heading: Vec2::from_angle(rand::thread_rng().gen_range(0.0..TAU)),
+        }
+    }
+}
+
+impl MapTile {
+    // NOTE: standing land fixed to the grid, platforms neither hold nor sever the island
+    pub fn is_land(&self) -> bool {
+        self.sinking == TileSinking::NotSinking && self.platform.is_none()
+    }
+}
+
+// NOTE: the map keeps the current position of a platform, so erosion and impacts find it
+pub(super) fn move_platforms(
+    mut query: Query<(&MapTileHandle, &mut Transform, &mut Platform), Without<SinkingMotion>>,
+    mut map: ResMut<WorldMap>,
+    des: Res<MapDescriptor>,
+    time: Res<Time>,
+) {
+    let dt = time.delta_seconds();
+    let bound = (des.radius as f32 + 4.0) * des.tile_size;
+    let land: Vec<Vec2> = map
+        .values()
+        .filter(|tile| tile.is_land())
+        .map(|tile| tile.position.xz())
+        .collect();
+    for (handle, mut trans, mut platform) in query.iter_mut() {
+        let Some(tile) = map.get_mut(handle) else {
+            continue;
+        };
+        match tile.platform {
+            Some(PlatformMotion::Path { speed, .. }) => {
+                let target = platform.waypoints[platform.leg];
+                let step = speed * des.tile_size * dt;
+                let offset = target - trans.translation;
+                if offset.length() > step {
+                    trans.translation += offset.normalize() * step;
+                    tile.position = trans.translation;
+                    continue;
+                }
+                trans.translation = target;
+                // ping-pong between the ends of the path
+                let last = platform.waypoints.len() - 1;
+                if platform.leg == last {
+                    platform.forward = false;
+                } else if platform.leg == 0 {
+                    platform.forward = true;
+                }
+                platform.leg = match platform.forward {
+                    true => (platform.leg + 1).min(last),
+                    false => platform.leg.saturating_sub(1),
+                };
+            }
+            Some(PlatformMotion::Turntable { speed }) => {
+                trans.rotate_y(speed.to_radians() * dt);
+            }
+            Some(PlatformMotion::Raft { speed }) => {
+                let next = trans.translation.xz() + platform.heading * speed * des.tile_size * dt;
+                let blocked = next.length() > bound
+                    || land
+                        .iter()
+                        .any(|pos| pos.distance(next) < des.tile_size - 0.01);
+                if blocked {
+                    // turn around with a bit of randomness, so rafts do not get stuck
+                    let turn = rand::thread_rng().gen_range(-1.0..1.0);
+                    platform.heading = Vec2::from_angle(turn).rotate(-platform.heading);
+                    continue;
+                }
+                trans.translation.x = next.x;
+                trans.translation.z = next.y;
+            }
+            None => continue,
+        }
+        tile.position = trans.translation;
+    }
+}
@@ -0,0 +1,158 @@
+use bevy::prelude::*;
+use bevy_rapier3d::dynamics::RigidBody;
+
+use super::{
+    chunks::TileSplitter, refresh_leafs, sinking::SinkingMotion, LeafTiles, MapDescriptor, MapTile,
+    MapTileHandle, TileKind, TileSinking, TileSunk, WorldMap,
+};
+
+// NOTE: asks the map to raise a tile, e.g. bought with coins by a player
+#[derive(Event, Clone, Copy, Debug)]
+pub struct RaiseTile {
+    pub handle: MapTileHandle,
+}
+
+// NOTE: sunk tiles waiting to re-emerge
+#[derive(Resource, Default)]
+pub(super) struct RegrowQueue(Vec<(MapTileHandle, Timer)>);
+
+// NOTE: a raised tile moves up out of the water until it reaches its position
+#[derive(Component)]
+pub(super) struct Emerging {
+    target: Vec3,
+}
+
+impl MapDescriptor {
+    // NOTE: the free spot next to land around `from` that is closest to `towards`
+    pub fn raise_target(
+        &self,
+        map: &WorldMap,
+        from: MapTileHandle,
+        towards: Vec3,
+    ) -> Option<MapTileHandle> {
+        let standing = |handle: &MapTileHandle| map.get(handle).is_some_and(MapTile::is_land);
+        let free = |handle: &MapTileHandle| {
+            !map.get(handle)
+                .is_some_and(|tile| tile.sinking != TileSinking::Sunk)
+        };
+        self.grid
+            .neighbours(from)
+            .into_iter()
+            .flat_map(|n| self.grid.neighbours(n).into_iter().chain([n]))
+            .filter(|handle| free(handle))
+            .filter(|handle| self.grid.neighbours(*handle).iter().any(standing))
+            .min_by(|a, b| {
+                let a = self.tile_position(*a).xz().distance(towards.xz());
+                let b = self.tile_position(*b).xz().distance(towards.xz());
+                a.total_cmp(&b)
+            })
+    }
+}
+
+pub(super) fn queue_regrowth(
+    mut sunk_events: EventReader<TileSunk>,
+    mut queue: ResMut<RegrowQueue>,
+    des: Res<MapDescriptor>,
+) {
+    for event in sunk_events.read() {
+        let Some(delay) = des.regrowth else {
+            continue;
+        };
+        queue
+            .0
+            .push((event.handle, Timer::new(delay, TimerMode::Once)));
+    }
+}
+
+pub(super) fn tick_regrowth(
+    mut queue: ResMut<RegrowQueue>,
+    mut raise_events: EventWriter<RaiseTile>,
+    time: Res<Time>,
+) {
+    queue.0.retain_mut(|(handle, timer)| {
+        if !timer.tick(time.delta()).finished() {
+            return true;
+        }
+        raise_events.send(RaiseTile { handle: *handle });
+        false
+    });
+}
+
+pub(super) fn raise_tiles(
+    mut raise_events: EventReader<RaiseTile>,
+    mut splitter: TileSplitter,
+    mut map: ResMut<WorldMap>,
+    mut leafs: ResMut<LeafTiles>,
+    des: Res<MapDescriptor>,
+) {
+    for &RaiseTile { handle } in raise_events.read() {
+        if map
+            .get(&handle)
+            .is_some_and(|tile| tile.sinking != TileSinking::Sunk)
+        {
+            continue;
+        }
+        // new land is raised to the height of the surrounding tiles
+        let height = des
+            .grid
+            .neighbours(handle)
+            .iter()
+            .filter_map(|n| map.get(n))
+            .filter(|tile| tile.is_land())
+            .map(|tile| tile.elevation)
+            .reduce(f32::max)
+            .unwrap_or(0.0);
+        // sunk platforms come back as plain land on their grid position
+        let sunk = map.remove(&handle).filter(|sunk| sunk.platform.is_none());
+        let tile = match sunk {
+            Some(sunk) => MapTile {
+                constitution: sunk.max_constitution,
+                sinking: TileSinking::NotSinking,
+                crumble: None,
+                ..sunk
+            },
+            None => MapTile {
+                position: des.tile_position(handle) + Vec3::Y * height,
+                elevation: height,
+                ramp: None,
+                platform: None,
+                constitution: 1.0,
+                max_constitution: 1.0,
+                sinking: TileSinking::NotSinking,
+                kind: TileKind::Grass,
+                crumble: None,
+            },
+        };
+        let target = tile.position;
+        // raised columns start with their top below the water as well
+        let depth = des.tile_height + tile.elevation.max(0.0);
+        splitter
+            .split(handle, &tile)
+            .insert(RigidBody::KinematicPositionBased)
+            .insert(Transform::from_translation(target - Vec3::Y * depth))
+            .insert(Emerging { target });
+        map.insert(handle, tile);
+        refresh_leafs(&mut leafs, &map, des.grid, handle);
+    }
+}
+
+pub(super) fn emerge_tiles(
+    mut cmd: Commands,
+    mut query: Query<(Entity, &mut Transform, &mut RigidBody, &Emerging), Without<SinkingMotion>>,
+    des: Res<MapDescriptor>,
+    time: Res<Time>,
+) {
+    for (entity, mut trans, mut body, emerging) in query.iter_mut() {
+        trans.translation.y += des.emerge_speed * time.delta_seconds();
+        if trans.translation.y < emerging.target.y {
+            continue;
+        }
+        trans.translation = emerging.target;
+        *body = RigidBody::Fixed;
+        cmd.entity(entity).remove::<Emerging>();
+    }
+}
+
+pub(super) fn clear_regrowth(mut queue: ResMut<RegrowQueue>) {
+    queue.0.clear();
+}
@@ -0,0 +1,127 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use bevy_rapier3d::dynamics::RigidBody;
+use rand::Rng;
+
+use super::{
+    chunks::{MapEntities, TileSplitter},
+    connectivity::FragmentSevered,
+    MapDescriptor, MapTileHandle, TileEvents, TileSinking, TileStartedSinking, WorldMap,
+};
+
+// NOTE: a sinking tile tips over around `pivot` while it drops, severed fragments share one pivot
+#[derive(Component, Clone, Copy, Debug)]
+pub struct SinkingMotion {
+    origin: Vec3,
+    pivot: Vec3,
+    axis: Vec3,
+    drop: f32,
+    velocity: f32,
+    elapsed: f32,
+}
+
+impl SinkingMotion {
+    fn new(origin: Vec3, pivot: Vec3, axis: Vec3) -> Self {
+        Self {
+            origin,
+            pivot,
+            axis,
+            drop: 0.0,
+            velocity: 0.0,
+            elapsed: 0.0,
+        }
+    }
+}
+
+fn random_direction(rng: &mut impl Rng) -> Vec3 {
+    let dir = Vec2::from_angle(rng.gen_range(0.0..TAU));
+    Vec3::new(dir.x, 0.0, dir.y)
+}
+
+// NOTE: turns sinking tiles into kinematic bodies, so rapier carries the players standing on them
+pub(super) fn start_sinking_bodies(
+    mut started_events: EventReader<TileStartedSinking>,
+    mut severed_events: EventReader<FragmentSevered>,
+    mut splitter: TileSplitter,
+    map: Res<WorldMap>,
+    des: Res<MapDescriptor>,
+) {
+    if started_events.is_empty() {
+        return;
+    }
+    let mut rng = rand::thread_rng();
+    // fragments tip over the edge of the fragment in a random direction
+    let mut fragments = Vec::new();
+    for event in severed_events.read() {
+        let dir = random_direction(&mut rng);
+        let extent = event
+            .tiles
+            .iter()
+            .filter_map(|handle| map.get(handle))
+            .map(|tile| (tile.position - event.center).dot(dir))
+            .fold(0.0, f32::max);
+        let pivot = event.center + dir * (extent + des.tile_size / 2.0);
+        fragments.push((event, pivot, dir));
+    }
+    let started: Vec<MapTileHandle> = started_events.read().map(|event| event.handle).collect();
+
+    for handle in started.iter() {
+        let Some(tile) = map.get(handle) else {
+            continue;
+        };
+        let origin = tile.position;
+        let fragment = fragments
+            .iter()
+            .find(|(fragment, _, _)| fragment.tiles.contains(handle));
+        let motion = match fragment {
+            Some((_, pivot, dir)) => SinkingMotion::new(origin, *pivot, dir.cross(Vec3::Y)),
+            // single tiles tip over one of their edges
+            None => {
+                let dir = random_direction(&mut rng);
+                let pivot = origin + dir * des.tile_size / 2.0;
+                SinkingMotion::new(origin, pivot, dir.cross(Vec3::Y))
+            }
+        };
+        // tiles of a chunk are split out so they can move on their own
+        splitter
+            .split(*handle, tile)
+            .insert(RigidBody::KinematicPositionBased)
+            .insert(motion);
+    }
+}
+
+// NOTE: Handle the logic for sinking a tile
+pub(super) fn sink_tile(
+    mut cmd: Commands,
+    mut query: Query<(Entity, &MapTileHandle, &mut Transform, &mut SinkingMotion)>,
+    mut map: ResMut<WorldMap>,
+    mut entities: ResMut<MapEntities>,
+    time: Res<Time>,
+    des: Res<MapDescriptor>,
+    mut events: TileEvents,
+) {
+    let dt = time.delta_seconds();
+    for (entity, handle, mut trans, mut motion) in query.iter_mut() {
+        let Some(tile) = map.get_mut(handle) else {
+            continue;
+        };
+        if TileSinking::Sinking != tile.sinking {
+            continue;
+        }
+        if trans.translation.y < -3.0 {
+            events.sunk(*handle, tile);
+            entities.remove_tile(*handle);
+            cmd.entity(entity).despawn_recursive();
+            continue;
+        }
+        motion.elapsed += dt;
+        motion.velocity += des.sink_acceleration * dt;
+        motion.drop += motion.velocity * dt;
+        let angle = (motion.elapsed * des.sink_tilt_speed).min(des.sink_max_tilt);
+        let rotation = Quat::from_axis_angle(motion.axis, angle);
+        trans.translation =
+            motion.pivot + rotation * (motion.origin - motion.pivot) - Vec3::Y * motion.drop;
+        trans.rotation = rotation;
+    }
+}
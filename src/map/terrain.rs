@@ -0,0 +1,148 @@
+use bevy::{prelude::*, render::mesh::VertexAttributeValues, utils::HashMap};
+use bevy_rapier3d::geometry::Collider;
+use serde::Deserialize;
+
+use super::{grid::GridKind, MapDescriptor, MapTile};
+
+// NOTE: the top of a ramp climbs by `rise` towards the side at `angle` degrees
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub struct Ramp {
+    pub angle: f32,
+    pub rise: f32,
+}
+
+impl Ramp {
+    pub fn direction(&self) -> Vec2 {
+        Vec2::from_angle(self.angle.to_radians())
+    }
+}
+
+// NOTE: tiles share mesh and collider if they have the same elevation and ramp, rounded to centimetres
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct ShapeKey {
+    elevation: i32,
+    ramp: Option<(i32, i32)>,
+}
+
+impl ShapeKey {
+    fn new(tile: &MapTile) -> Self {
+        let cm = |value: f32| (value * 100.0).round() as i32;
+        Self {
+            elevation: cm(tile.elevation),
+            ramp: tile.ramp.map(|ramp| (cm(ramp.angle), cm(ramp.rise))),
+        }
+    }
+}
+
+pub(crate) struct TileShape {
+    pub mesh: Handle<Mesh>,
+    // NOTE: copied into the merged chunk meshes
+    pub base_mesh: Mesh,
+    pub collider: Collider,
+}
+
+// NOTE: meshes and colliders of all tile shapes on the current map
+#[derive(Resource)]
+pub(crate) struct TileAssets {
+    grid: GridKind,
+    tile_size: f32,
+    tile_height: f32,
+    shapes: HashMap<ShapeKey, TileShape>,
+}
+
+impl TileAssets {
+    pub fn new(des: &MapDescriptor) -> Self {
+        Self {
+            grid: des.grid,
+            tile_size: des.tile_size,
+            tile_height: des.tile_height,
+            shapes: HashMap::new(),
+        }
+    }
+
+    pub fn get(&self, tile: &MapTile) -> Option<&TileShape> {
+        self.shapes.get(&ShapeKey::new(tile))
+    }
+
+    pub fn insert(&mut self, tile: &MapTile, meshes: &mut Assets<Mesh>) -> &TileShape {
+        self.shapes.entry(ShapeKey::new(tile)).or_insert_with(|| {
+            let base_mesh = shaped_mesh(
+                self.grid,
+                self.tile_size,
+                self.tile_height,
+                tile.elevation,
+                tile.ramp,
+            );
+            // flat tiles at ground level keep the simple collider of the grid
+            let collider = if tile.elevation <= 0.0 && tile.ramp.is_none() {
+                self.grid.tile_collider(self.tile_size, self.tile_height)
+            } else {
+                shaped_collider(&base_mesh)
+            };
+            TileShape {
+                mesh: meshes.add(base_mesh.clone()),
+                base_mesh,
+                collider,
+            }
+        })
+    }
+}
+
+// NOTE: raised tiles reach down to the ground as a column, the top stays at the tile position
+fn shaped_mesh(
+    grid: GridKind,
+    tile_size: f32,
+    tile_height: f32,
+    elevation: f32,
+    ramp: Option<Ramp>,
+) -> Mesh {
+    let column = elevation.max(0.0);
+    let mut mesh = grid
+        .tile_mesh(tile_size, tile_height + column)
+        .translated_by(Vec3::NEG_Y * column / 2.0);
+    let Some(ramp) = ramp else {
+        return mesh;
+    };
+    let dir = ramp.direction();
+    let extent = grid.extent(dir, tile_size);
+    let top = tile_height / 2.0;
+    if let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute_mut(Mesh::ATTRIBUTE_POSITION)
+    {
+        for position in positions.iter_mut() {
+            if position[1] < top - f32::EPSILON {
+                continue;
+            }
+            let t = 0.5 + Vec2::new(position[0], position[2]).dot(dir) / (2.0 * extent);
+            position[1] += ramp.rise * t.clamp(0.0, 1.0);
+        }
+    }
+    // the sloped top needs its own normals
+    mesh.duplicate_vertices();
+    mesh.compute_flat_normals();
+    mesh
+}
+
+fn shaped_collider(mesh: &Mesh) -> Collider {
+    let points: Vec<Vec3> = match mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+        Some(VertexAttributeValues::Float32x3(positions)) => {
+            positions.iter().map(|p| Vec3::from(*p)).collect()
+        }
+        _ => vec![],
+    };
+    Collider::convex_hull(&points).expect("tile shape has a valid convex hull")
+}
+
+impl MapDescriptor {
+    // NOTE: height of the walkable top of `tile` at `point`, ramps are interpolated
+    pub fn surface_height(&self, tile: &MapTile, point: Vec3) -> f32 {
+        let top = tile.position.y + self.tile_height / 2.0;
+        let Some(ramp) = tile.ramp else {
+            return top;
+        };
+        let dir = ramp.direction();
+        let extent = self.grid.extent(dir, self.tile_size);
+        let t = 0.5 + (point - tile.position).xz().dot(dir) / (2.0 * extent);
+        top + ramp.rise * t.clamp(0.0, 1.0)
+    }
+}
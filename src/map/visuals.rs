@@ -0,0 +1,244 @@
+use bevy::{
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+    },
+    utils::HashMap,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::{
+    MapDescriptor, MapTile, MapTileHandle, TileDamaged, TileKind, TileSinking, TileStartedSinking,
+    TileSunk, WorldMap,
+};
+
+// NOTE: intact, three levels of cracks
+pub const DAMAGE_STAGES: usize = 4;
+const CRACK_TEXTURE_SIZE: u32 = 64;
+
+// NOTE: the visible mesh of a tile, a child of the tile so shaking it leaves the collider alone
+#[derive(Component, Clone, Copy, Debug)]
+pub struct TileVisual(pub MapTileHandle);
+
+// NOTE: one material per tile kind and damage stage, shared by all tiles
+#[derive(Resource, Default)]
+pub struct TileMaterials(HashMap<(TileKind, usize), Handle<StandardMaterial>>);
+
+impl TileMaterials {
+    pub fn get(&self, kind: TileKind, stage: usize) -> Handle<StandardMaterial> {
+        self.0[&(kind, stage.min(DAMAGE_STAGES - 1))].clone()
+    }
+}
+
+#[derive(Resource)]
+pub(super) struct DustAssets {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+}
+
+#[derive(Component)]
+pub(super) struct DustParticle {
+    velocity: Vec3,
+    lifetime: Timer,
+}
+
+impl MapTile {
+    pub fn damage_stage(&self) -> usize {
+        let health = (self.constitution / self.max_constitution).clamp(0.0, 1.0);
+        (((1.0 - health) * DAMAGE_STAGES as f32) as usize).min(DAMAGE_STAGES - 1)
+    }
+
+    // NOTE: 0 for a safe tile up to 1 right before the tile starts sinking
+    pub fn warning(&self, warning_constitution: f32) -> f32 {
+        if self.sinking != TileSinking::NotSinking {
+            return 0.0;
+        }
+        let crumbling = self.crumble.as_ref().map_or(0.0, |timer| timer.fraction());
+        let eroding = 1.0 - self.constitution / warning_constitution;
+        crumbling.max(eroding).clamp(0.0, 1.0)
+    }
+}
+
+// NOTE: random walks of dark pixels on white, the texture is multiplied with the tile color
+fn crack_texture(stage: usize) -> Image {
+    let size = CRACK_TEXTURE_SIZE as i32;
+    let mut data = vec![255u8; (size * size * 4) as usize];
+    let mut rng = StdRng::seed_from_u64(stage as u64);
+    for _ in 0..stage * 3 {
+        let (mut x, mut y) = (rng.gen_range(0..size), rng.gen_range(0..size));
+        let mut dir = rng.gen_range(0.0..std::f32::consts::TAU);
+        for _ in 0..rng.gen_range(size / 2..size) {
+            let i = ((y * size + x) * 4) as usize;
+            data[i..i + 3].copy_from_slice(&[40, 35, 30]);
+            dir += rng.gen_range(-0.6..0.6);
+            x = (x + dir.cos().round() as i32).rem_euclid(size);
+            y = (y + dir.sin().round() as i32).rem_euclid(size);
+        }
+    }
+    Image::new(
+        Extent3d {
+            width: CRACK_TEXTURE_SIZE,
+            height: CRACK_TEXTURE_SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+}
+
+pub(super) fn setup_tile_visuals(
+    mut cmd: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let textures: Vec<Option<Handle<Image>>> = (0..DAMAGE_STAGES)
+        .map(|stage| (stage > 0).then(|| images.add(crack_texture(stage))))
+        .collect();
+    let mut tile_materials = HashMap::new();
+    for kind in TileKind::ALL {
+        for (stage, texture) in textures.iter().enumerate() {
+            let mut material = kind.material();
+            // darken the tile a bit more with every stage
+            let darken = 1.0 - 0.15 * stage as f32;
+            material.base_color = kind.color() * darken;
+            material.base_color.set_a(1.0);
+            material.base_color_texture = texture.clone();
+            tile_materials.insert((kind, stage), materials.add(material));
+        }
+    }
+    cmd.insert_resource(TileMaterials(tile_materials));
+
+    cmd.insert_resource(DustAssets {
+        mesh: meshes.add(Cuboid::from_size(Vec3::splat(0.15))),
+        material: materials.add(StandardMaterial {
+            base_color: Color::rgba(0.75, 0.65, 0.5, 0.7),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        }),
+    });
+}
+
+pub(super) fn update_tile_materials(
+    mut events: EventReader<TileDamaged>,
+    mut query: Query<(&TileVisual, &mut Handle<StandardMaterial>)>,
+    map: Res<WorldMap>,
+    tile_materials: Res<TileMaterials>,
+) {
+    if events.is_empty() {
+        return;
+    }
+    let damaged: Vec<MapTileHandle> = events.read().map(|event| event.handle).collect();
+    for (visual, mut material) in query.iter_mut() {
+        if !damaged.contains(&visual.0) {
+            continue;
+        }
+        let Some(tile) = map.get(&visual.0) else {
+            continue;
+        };
+        let stage_material = tile_materials.get(tile.kind, tile.damage_stage());
+        if *material != stage_material {
+            *material = stage_material;
+        }
+    }
+}
+
+// NOTE: tiles about to sink shake harder the closer they are to going under
+pub(super) fn wobble_tiles(
+    mut query: Query<(&TileVisual, &mut Transform)>,
+    map: Res<WorldMap>,
+    des: Res<MapDescriptor>,
+    time: Res<Time>,
+) {
+    let t = time.elapsed_seconds();
+    for (visual, mut trans) in query.iter_mut() {
+        let Some(tile) = map.get(&visual.0) else {
+            continue;
+        };
+        let warning = tile.warning(des.warning_constitution);
+        if warning <= 0.0 {
+            if *trans != Transform::IDENTITY {
+                *trans = Transform::IDENTITY;
+            }
+            continue;
+        }
+        let phase = visual.0.x as f32 * 12.9898 + visual.0.z as f32 * 78.233;
+        let strength = warning * warning;
+        trans.translation =
+            Vec3::new((t * 37.0 + phase).sin(), 0.0, (t * 41.0 + phase).cos()) * 0.06 * strength;
+        trans.rotation = Quat::from_euler(
+            EulerRot::XYZ,
+            (t * 23.0 + phase).sin() * 0.06 * strength,
+            0.0,
+            (t * 29.0 + phase).cos() * 0.06 * strength,
+        );
+    }
+}
+
+fn spawn_dust(
+    cmd: &mut Commands,
+    dust: &DustAssets,
+    des: &MapDescriptor,
+    center: Vec3,
+    count: usize,
+    rng: &mut impl Rng,
+) {
+    let half = des.tile_size / 2.0;
+    for _ in 0..count {
+        let offset = Vec3::new(rng.gen_range(-half..half), 0.0, rng.gen_range(-half..half));
+        let velocity = Vec3::new(offset.x, rng.gen_range(1.0..2.5), offset.z);
+        cmd.spawn((
+            PbrBundle {
+                mesh: dust.mesh.clone(),
+                material: dust.material.clone(),
+                transform: Transform::from_translation(center + offset),
+                ..default()
+            },
+            DustParticle {
+                velocity,
+                lifetime: Timer::from_seconds(rng.gen_range(0.6..1.2), TimerMode::Once),
+            },
+        ));
+    }
+}
+
+pub(super) fn spawn_sinking_dust(
+    mut cmd: Commands,
+    mut started_events: EventReader<TileStartedSinking>,
+    mut sunk_events: EventReader<TileSunk>,
+    dust: Res<DustAssets>,
+    des: Res<MapDescriptor>,
+) {
+    let mut rng = rand::thread_rng();
+    let top = Vec3::Y * des.tile_height / 2.0;
+    for event in started_events.read() {
+        spawn_dust(&mut cmd, &dust, &des, event.position + top, 12, &mut rng);
+    }
+    // NOTE: sunk tiles are under water already, the dust marks where they went down
+    for event in sunk_events.read() {
+        let surface = Vec3::new(event.position.x, 0.0, event.position.z);
+        spawn_dust(&mut cmd, &dust, &des, surface, 6, &mut rng);
+    }
+}
+
+pub(super) fn update_dust(
+    mut cmd: Commands,
+    mut query: Query<(Entity, &mut DustParticle, &mut Transform)>,
+    time: Res<Time>,
+) {
+    let dt = time.delta_seconds();
+    for (entity, mut particle, mut trans) in query.iter_mut() {
+        if particle.lifetime.tick(time.delta()).finished() {
+            cmd.entity(entity).despawn();
+            continue;
+        }
+        particle.velocity.y -= 3.0 * dt;
+        particle.velocity *= 1.0 - 2.0 * dt;
+        trans.translation += particle.velocity * dt;
+        trans.scale = Vec3::splat(1.0 - particle.lifetime.fraction());
+    }
+}
@@ -33,6 +33,7 @@ fn limit_max_velocity(mut query: Query<(&PlayerActionState, &mut Velocity,), Wit
             PlayerActionState::Shield(_) => {
                 velocity.linvel = limit_horizontal_velocity(velocity.linvel, 2.0)
             }
+            _ => {}
         }
     }
 }
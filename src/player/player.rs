@@ -2,6 +2,11 @@ use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
 use std::time::Duration;
 
+use crate::{
+    input::PlayerInput,
+    map::{GroundTile, MapTileHandle},
+};
+
 #[derive(Bundle)]
 pub struct PlayerPhysicsBundle {
     rigid_body: RigidBody,
@@ -12,6 +17,7 @@ pub struct PlayerPhysicsBundle {
     active_events: ActiveEvents,
     external_force: ExternalForce,
     external_impulse: ExternalImpulse,
+    velocity: Velocity,
     locked_axes: LockedAxes,
 }
 
@@ -32,6 +38,7 @@ impl Default for PlayerPhysicsBundle {
             active_events: ActiveEvents::COLLISION_EVENTS,
             external_force: ExternalForce::default(),
             external_impulse: ExternalImpulse::default(),
+            velocity: Velocity::default(),
             locked_axes: LockedAxes::ROTATION_LOCKED,
         }
     }
@@ -42,6 +49,9 @@ pub struct PlayerBundle {
     player: Player,
     player_state: PlayerActionState,
     grounded: Grounded,
+    ground_tile: GroundTile,
+    input: PlayerInput,
+    damage: DamagePercent,
 }
 
 impl Default for PlayerBundle {
@@ -50,6 +60,9 @@ impl Default for PlayerBundle {
             player: Player,
             player_state: PlayerActionState::Idle,
             grounded: Grounded::Airborne,
+            ground_tile: GroundTile::default(),
+            input: PlayerInput::default(),
+            damage: DamagePercent::default(),
         }
     }
 }
@@ -57,12 +70,21 @@ impl Default for PlayerBundle {
 #[derive(Component)]
 pub struct Player;
 
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PlayerSlot(pub usize);
+
+// NOTE: rises with every hit taken, the higher it is the further the player flies
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct DamagePercent(pub f32);
+
 #[derive(Component, Clone, Debug)]
 pub enum PlayerActionState {
     Idle,
     PrepareAttack(Duration),
     Attack(Duration, f32), //secound value is attack strength
     Shield(Duration),
+    Stunned(Duration),
+    GroundPound,
 }
 
 #[derive(Resource)]
@@ -72,6 +94,36 @@ pub struct PlayerActionValues {
     pub parry_duration: Duration,
     pub min_prepare_duration: Duration,
     pub max_prepare_duration: Duration,
+    pub stun_duration: Duration,
+    pub shield_absorption: f32,
+    pub ground_pound_impulse: f32,
+    pub ground_pound_damage: f32,
+    pub ground_pound_radius: f32,
+    pub heavy_landing_speed: f32,
+    pub landing_damage: f32,
+    pub attack_ground_damage: f32,
+    // damage percent dealt by a fully charged attack
+    pub hit_damage: f32,
+    // extra knockback per damage percent
+    pub knockback_growth: f32,
+}
+
+// NOTE: sent when a player touches the ground after being airborne
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PlayerLanded {
+    pub player: Entity,
+    pub tile: Option<MapTileHandle>,
+    pub position: Vec3,
+    pub speed: f32,
+}
+
+// NOTE: sent when a charged attack pushes another player, also when the hit is blocked
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PlayerHit {
+    pub attacker: Entity,
+    pub target: Entity,
+    pub impulse: Vec3,
+    pub strength: f32,
 }
 
 #[derive(Component, Clone, Debug)]
@@ -88,6 +140,16 @@ impl Default for PlayerActionValues {
             parry_duration: Duration::from_millis(400),
             min_prepare_duration: Duration::from_millis(400),
             max_prepare_duration: Duration::from_millis(3000),
+            stun_duration: Duration::from_millis(1200),
+            shield_absorption: 0.8,
+            ground_pound_impulse: 16.0,
+            ground_pound_damage: 0.6,
+            ground_pound_radius: 3.0,
+            heavy_landing_speed: 10.0,
+            landing_damage: 0.03,
+            attack_ground_damage: 0.3,
+            hit_damage: 20.0,
+            knockback_growth: 0.01,
         }
     }
 }
@@ -105,12 +167,27 @@ impl PlayerActionValues {
         d / range
     }
 
+    pub fn hit_damage(&self, strength: f32, shielded: bool) -> f32 {
+        let absorbed = if shielded {
+            self.shield_absorption
+        } else {
+            0.0
+        };
+        self.hit_damage * strength * (1.0 - absorbed)
+    }
+
+    pub fn knockback_scale(&self, damage: &DamagePercent) -> f32 {
+        1.0 + damage.0 * self.knockback_growth
+    }
+
     pub fn move_multipier(&self, state: &PlayerActionState) -> f32 {
         match state {
             PlayerActionState::Idle => 24.0,
             PlayerActionState::PrepareAttack(_) => 12.0,
             PlayerActionState::Attack(_, _) => 32.0,
             PlayerActionState::Shield(_) => 12.0,
+            PlayerActionState::Stunned(_) => 0.0,
+            PlayerActionState::GroundPound => 0.0,
         }
     }
 }
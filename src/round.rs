@@ -0,0 +1,304 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    coin::Coin,
+    coin_collector::CoinCollection,
+    input::{ActionSample, PlayerInput},
+    map::{erosion::ErosionFocus, navigation::NavGraph, MapState},
+    DamagePercent, Player, PlayerActionState,
+};
+
+#[derive(Default)]
+pub struct RoundPlugin {
+    descriptor: RoundDescriptor,
+}
+
+#[derive(Resource, Clone)]
+pub struct RoundDescriptor {
+    elimination_height: f32,
+    restart_delay: Duration,
+    // NOTE: lives per player, a player falling with the last stock is eliminated
+    pub stocks: u32,
+    respawn_height: f32,
+    pub respawn_platform_duration: Duration,
+}
+
+impl Default for RoundDescriptor {
+    fn default() -> Self {
+        Self {
+            elimination_height: 0.0,
+            restart_delay: Duration::from_millis(3000),
+            stocks: 3,
+            respawn_height: 6.0,
+            respawn_platform_duration: Duration::from_millis(3000),
+        }
+    }
+}
+
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Stocks(pub u32);
+
+// NOTE: hits do not push or damage the player, given while on the respawn platform
+#[derive(Component)]
+pub struct Invulnerable;
+
+// NOTE: carries a respawned player until it dissolves after a while or the player moves
+#[derive(Component)]
+struct RespawnPlatform {
+    player: Entity,
+    timer: Timer,
+}
+
+#[derive(Resource)]
+struct RespawnPlatformAssets {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PlayerEliminated {
+    pub player: Entity,
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PlayerLostStock {
+    pub player: Entity,
+    pub stocks_left: u32,
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PlayerRespawned {
+    pub player: Entity,
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct RoundOver {
+    pub winner: Option<Entity>,
+}
+
+#[derive(Resource, Deref, DerefMut)]
+struct RestartTimer(Timer);
+
+impl Plugin for RoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.descriptor.clone())
+            .add_event::<PlayerEliminated>()
+            .add_event::<PlayerLostStock>()
+            .add_event::<PlayerRespawned>()
+            .add_event::<RoundOver>()
+            .add_systems(Startup, setup_respawn_platform)
+            .add_systems(
+                Update,
+                (
+                    give_stocks,
+                    check_round_over
+                        .run_if(not(resource_exists::<RestartTimer>))
+                        .after(give_stocks),
+                    respawn_players.after(check_round_over),
+                    dissolve_respawn_platforms.after(respawn_players),
+                    tick_restart_timer.run_if(resource_exists::<RestartTimer>),
+                    track_leading_player,
+                )
+                    .run_if(in_state(MapState::GamePlay)),
+            )
+            .add_systems(OnEnter(MapState::DespawnMap), despawn_round_entities);
+    }
+}
+
+fn setup_respawn_platform(
+    mut cmd: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    cmd.insert_resource(RespawnPlatformAssets {
+        mesh: meshes.add(Cuboid::new(3.0, 0.3, 3.0)),
+        material: materials.add(StandardMaterial {
+            base_color: Color::rgba(0.6, 0.9, 1.0, 0.6),
+            alpha_mode: AlphaMode::Blend,
+            ..default()
+        }),
+    });
+}
+
+fn give_stocks(mut cmd: Commands, query: Query<Entity, Added<Player>>, des: Res<RoundDescriptor>) {
+    for entity in query.iter() {
+        cmd.entity(entity).insert(Stocks(des.stocks));
+    }
+}
+
+// NOTE: players that fell into the water lose a stock, without stocks left they are eliminated
+// the round ends once at most one is left
+fn check_round_over(
+    mut cmd: Commands,
+    mut query: Query<(Entity, &Transform, Option<&mut Stocks>), With<Player>>,
+    mut eliminated_events: EventWriter<PlayerEliminated>,
+    mut lost_stock_events: EventWriter<PlayerLostStock>,
+    mut round_over_events: EventWriter<RoundOver>,
+    des: Res<RoundDescriptor>,
+) {
+    let num_players = query.iter().len();
+    let mut alive = Vec::new();
+    for (entity, trans, stocks) in query.iter_mut() {
+        if trans.translation.y > des.elimination_height {
+            alive.push(entity);
+            continue;
+        }
+        if let Some(mut stocks) = stocks.filter(|stocks| stocks.0 > 1) {
+            stocks.0 -= 1;
+            info!("Player {:?} lost a stock, {} left.", entity, stocks.0);
+            lost_stock_events.send(PlayerLostStock {
+                player: entity,
+                stocks_left: stocks.0,
+            });
+            alive.push(entity);
+            continue;
+        }
+        info!("Player {:?} has been eliminated!", entity);
+        cmd.entity(entity).despawn_recursive();
+        eliminated_events.send(PlayerEliminated { player: entity });
+    }
+
+    // a single player plays until they fall, otherwise the last one standing wins
+    if alive.len() >= num_players.min(2) {
+        return;
+    }
+    let winner = alive.first().copied();
+    match winner {
+        Some(winner) => info!("Player {:?} wins the round!", winner),
+        None => info!("Round is over, nobody wins!"),
+    }
+    round_over_events.send(RoundOver { winner });
+    cmd.insert_resource(RestartTimer(Timer::new(
+        des.restart_delay,
+        TimerMode::Once,
+    )));
+}
+
+// NOTE: the player is put back fresh above the safest tile closest to the island centre, on a
+// platform of its own. Without standing land the platform is put above the origin
+fn respawn_players(
+    mut cmd: Commands,
+    mut query: Query<(
+        &mut Transform,
+        &mut Velocity,
+        &mut ExternalImpulse,
+        &mut PlayerActionState,
+        &mut DamagePercent,
+    )>,
+    mut lost_stock_events: EventReader<PlayerLostStock>,
+    mut respawned_events: EventWriter<PlayerRespawned>,
+    assets: Res<RespawnPlatformAssets>,
+    graph: Res<NavGraph>,
+    des: Res<RoundDescriptor>,
+) {
+    let lost_stocks: Vec<&PlayerLostStock> = lost_stock_events.read().collect();
+    // players falling in the same frame get platforms above different tiles
+    let spawn_points = graph.spawn_points(lost_stocks.len());
+    for (i, lost) in lost_stocks.into_iter().enumerate() {
+        let Ok((mut trans, mut velocity, mut impulse, mut state, mut damage)) =
+            query.get_mut(lost.player)
+        else {
+            continue;
+        };
+        let ground = spawn_points.get(i).copied().unwrap_or_default();
+        let position = ground + Vec3::Y * des.respawn_height;
+        trans.translation = position + Vec3::Y;
+        *velocity = Velocity::zero();
+        impulse.impulse = Vec3::ZERO;
+        *state = PlayerActionState::Idle;
+        *damage = DamagePercent::default();
+        cmd.entity(lost.player).insert(Invulnerable);
+        cmd.spawn((
+            PbrBundle {
+                mesh: assets.mesh.clone(),
+                material: assets.material.clone(),
+                transform: Transform::from_translation(position),
+                ..default()
+            },
+            RigidBody::Fixed,
+            Collider::cuboid(1.5, 0.15, 1.5),
+            RespawnPlatform {
+                player: lost.player,
+                timer: Timer::new(des.respawn_platform_duration, TimerMode::Once),
+            },
+        ));
+        respawned_events.send(PlayerRespawned {
+            player: lost.player,
+        });
+    }
+}
+
+fn dissolve_respawn_platforms(
+    mut cmd: Commands,
+    mut query: Query<(Entity, &mut RespawnPlatform)>,
+    players: Query<&PlayerInput>,
+    time: Res<Time>,
+) {
+    for (entity, mut platform) in query.iter_mut() {
+        platform.timer.tick(time.delta());
+        // NOTE: the first input ends the protection, standing still does not
+        let moved = players
+            .get(platform.player)
+            .map_or(true, |input| input.sample() != ActionSample::default());
+        if !platform.timer.finished() && !moved {
+            continue;
+        }
+        cmd.entity(entity).despawn_recursive();
+        if let Some(mut player) = cmd.get_entity(platform.player) {
+            player.remove::<Invulnerable>();
+        }
+    }
+}
+
+// NOTE: the leader has the most coins, on a tie the one closest to the island centre leads
+fn track_leading_player(
+    query: Query<(&Transform, &CoinCollection), With<Player>>,
+    mut focus: ResMut<ErosionFocus>,
+) {
+    focus.0 = query
+        .iter()
+        .max_by(|(a_trans, a_coins), (b_trans, b_coins)| {
+            a_coins.num.cmp(&b_coins.num).then(
+                b_trans
+                    .translation
+                    .xz()
+                    .length()
+                    .total_cmp(&a_trans.translation.xz().length()),
+            )
+        })
+        .map(|(trans, _)| trans.translation);
+}
+
+fn tick_restart_timer(
+    mut cmd: Commands,
+    mut timer: ResMut<RestartTimer>,
+    mut state: ResMut<NextState<MapState>>,
+    time: Res<Time>,
+) {
+    timer.tick(time.delta());
+    if !timer.finished() {
+        return;
+    }
+    cmd.remove_resource::<RestartTimer>();
+    state.set(MapState::DespawnMap);
+}
+
+fn despawn_round_entities(
+    mut cmd: Commands,
+    player_query: Query<Entity, With<Player>>,
+    coin_query: Query<Entity, With<Coin>>,
+    platform_query: Query<Entity, With<RespawnPlatform>>,
+) {
+    for entity in player_query
+        .iter()
+        .chain(coin_query.iter())
+        .chain(platform_query.iter())
+    {
+        cmd.entity(entity).despawn_recursive();
+    }
+    // NOTE: the round may be restarted before the restart delay ran out
+    cmd.remove_resource::<RestartTimer>();
+}
@@ -0,0 +1,204 @@
+use std::time::Duration;
+
+use bevy::{
+    diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin},
+    prelude::*,
+};
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{
+    key_bindings::{LocalPlayers, MAX_LOCAL_PLAYERS},
+    map::{
+        chunks::MapEntities, erosion::ErosionStrategy, grid::GridKind, layout::MapLayout,
+        MapChoice, MapDescriptor, MapSelection, MapState,
+    },
+    round::RoundDescriptor,
+};
+
+pub struct MapMenuPlugin;
+
+#[derive(Resource, Default)]
+pub struct MapMenu {
+    pub open: bool,
+}
+
+impl Plugin for MapMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MapMenu>().add_systems(
+            Update,
+            (
+                toggle_map_menu,
+                map_menu_ui,
+                round_menu_ui,
+                map_performance_ui,
+            )
+                .chain(),
+        );
+    }
+}
+
+fn toggle_map_menu(keys: Res<ButtonInput<KeyCode>>, mut menu: ResMut<MapMenu>) {
+    if keys.just_pressed(KeyCode::F2) {
+        menu.open = !menu.open;
+    }
+}
+
+fn map_menu_ui(
+    mut contexts: EguiContexts,
+    mut menu: ResMut<MapMenu>,
+    mut selection: ResMut<MapSelection>,
+    mut des: ResMut<MapDescriptor>,
+    layouts: Res<Assets<MapLayout>>,
+    map_state: Res<State<MapState>>,
+    mut next_state: ResMut<NextState<MapState>>,
+) {
+    if !menu.open {
+        return;
+    }
+    let mut layouts: Vec<_> = layouts.iter().collect();
+    layouts.sort_by(|(_, a), (_, b)| a.name.cmp(&b.name));
+
+    egui::Window::new("Map")
+        .open(&mut menu.open)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.selectable_value(&mut selection.selected, MapChoice::Circle, "Circle");
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut selection.selected, MapChoice::Procedural, "Procedural");
+                ui.label("Seed");
+                ui.add(egui::DragValue::new(&mut des.generator.seed));
+                if ui.button("Random").clicked() {
+                    des.generator.seed = rand::random();
+                    selection.selected = MapChoice::Procedural;
+                }
+            });
+            for (id, layout) in layouts {
+                ui.selectable_value(&mut selection.selected, MapChoice::Layout(id), &layout.name);
+            }
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label("Grid");
+                ui.selectable_value(&mut selection.grid, GridKind::Square, "Square");
+                ui.selectable_value(&mut selection.grid, GridKind::Hex, "Hex");
+            });
+            ui.horizontal(|ui| {
+                ui.label("Erosion");
+                ui.selectable_value(&mut selection.erosion, None, "Map default");
+                for strategy in [
+                    ErosionStrategy::Random,
+                    ErosionStrategy::Rings,
+                    ErosionStrategy::Wave { angle: 0.0 },
+                    ErosionStrategy::Hunt,
+                ] {
+                    let selected =
+                        selection.erosion.as_ref().map(|s| s.name()) == Some(strategy.name());
+                    if ui.selectable_label(selected, strategy.name()).clicked() && !selected {
+                        selection.erosion = Some(strategy);
+                    }
+                }
+                if let Some(ErosionStrategy::Wave { angle }) = &mut selection.erosion {
+                    ui.add(
+                        egui::DragValue::new(angle)
+                            .suffix("°")
+                            .clamp_range(0.0..=360.0),
+                    );
+                }
+            });
+            ui.horizontal(|ui| {
+                let mut regrowth = des.regrowth.is_some();
+                ui.checkbox(&mut regrowth, "Sunk tiles re-emerge after");
+                let mut seconds = des.regrowth.map_or(10.0, |delay| delay.as_secs_f32());
+                ui.add_enabled(
+                    regrowth,
+                    egui::DragValue::new(&mut seconds)
+                        .suffix(" s")
+                        .clamp_range(1.0..=120.0),
+                );
+                des.regrowth = regrowth.then(|| Duration::from_secs_f32(seconds));
+            });
+            ui.horizontal(|ui| {
+                let mut raising = des.raise_cost.is_some();
+                ui.checkbox(&mut raising, "Players raise tiles for");
+                let mut cost = des.raise_cost.unwrap_or(3);
+                ui.add_enabled(
+                    raising,
+                    egui::DragValue::new(&mut cost)
+                        .suffix(" coins")
+                        .clamp_range(1..=20),
+                );
+                des.raise_cost = raising.then_some(cost);
+            });
+            ui.horizontal(|ui| {
+                ui.label("Radius");
+                ui.add(egui::DragValue::new(&mut des.radius).clamp_range(2..=40));
+                ui.checkbox(&mut des.chunked, "Merge tiles into chunks");
+            });
+            ui.label("The selected map is played from the next round on.");
+            let playing = *map_state.get() == MapState::GamePlay;
+            if ui
+                .add_enabled(playing, egui::Button::new("Restart round"))
+                .clicked()
+            {
+                next_state.set(MapState::DespawnMap);
+            }
+        });
+}
+
+// NOTE: players and stocks are handed out when the players spawn, changes apply from the next round on
+fn round_menu_ui(
+    mut contexts: EguiContexts,
+    menu: Res<MapMenu>,
+    mut des: ResMut<RoundDescriptor>,
+    mut local_players: ResMut<LocalPlayers>,
+) {
+    if !menu.open {
+        return;
+    }
+    egui::Window::new("Round").show(contexts.ctx_mut(), |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Players");
+            ui.add(egui::DragValue::new(&mut local_players.num).clamp_range(1..=MAX_LOCAL_PLAYERS));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Stocks");
+            ui.add(egui::DragValue::new(&mut des.stocks).clamp_range(1..=9));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Respawn platform dissolves after");
+            let mut seconds = des.respawn_platform_duration.as_secs_f32();
+            ui.add(
+                egui::DragValue::new(&mut seconds)
+                    .suffix(" s")
+                    .speed(0.1)
+                    .clamp_range(0.5..=10.0),
+            );
+            des.respawn_platform_duration = Duration::from_secs_f32(seconds);
+        });
+    });
+}
+
+// NOTE: compares frame times of chunked and unchunked maps, toggle chunks and restart the round
+fn map_performance_ui(
+    mut contexts: EguiContexts,
+    menu: Res<MapMenu>,
+    diagnostics: Res<DiagnosticsStore>,
+    entities: Res<MapEntities>,
+) {
+    if !menu.open {
+        return;
+    }
+    let frame_time = diagnostics
+        .get(&FrameTimeDiagnosticsPlugin::FRAME_TIME)
+        .and_then(|frame_time| frame_time.smoothed());
+    let fps = diagnostics
+        .get(&FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.smoothed());
+    egui::Window::new("Map performance").show(contexts.ctx_mut(), |ui| {
+        ui.label(format!(
+            "Frame time: {:.2} ms ({:.0} fps)",
+            frame_time.unwrap_or_default(),
+            fps.unwrap_or_default()
+        ));
+        ui.label(format!("Chunks: {}", entities.num_chunks()));
+        ui.label(format!("Single tiles: {}", entities.num_tiles()));
+    });
+}
@@ -1,9 +1,111 @@
+pub mod map_menu;
+pub mod practice_menu;
+pub mod rebind_menu;
+pub mod training_hud;
+
+use std::time::Duration;
+
 use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{
+    round::{PlayerEliminated, PlayerLostStock, RoundOver, Stocks},
+    DamagePercent, Player, PlayerSlot,
+};
+
+pub struct PlayerUIPlugin;
 
-struct PlayerUIPlugin;
+// NOTE: short messages about the round, each shown for a few seconds
+#[derive(Resource, Default)]
+struct HudMessages(Vec<(String, Timer)>);
 
 impl Plugin for PlayerUIPlugin {
     fn build(&self, app: &mut App) {
-        todo!()
+        app.init_resource::<HudMessages>()
+            .add_systems(Update, (collect_hud_messages, player_hud_ui).chain());
+    }
+}
+
+fn player_name(slot: Option<&PlayerSlot>) -> String {
+    slot.map_or("Practice".to_string(), |slot| {
+        format!("Player {}", slot.0 + 1)
+    })
+}
+
+fn collect_hud_messages(
+    query: Query<Option<&PlayerSlot>, With<Player>>,
+    mut lost_stock_events: EventReader<PlayerLostStock>,
+    mut eliminated_events: EventReader<PlayerEliminated>,
+    mut round_over_events: EventReader<RoundOver>,
+    mut messages: ResMut<HudMessages>,
+    time: Res<Time>,
+) {
+    // eliminated players may already be despawned
+    let name = |entity: Entity| {
+        query
+            .get(entity)
+            .map_or("A player".to_string(), player_name)
+    };
+    let mut new_messages: Vec<String> = vec![];
+    for lost in lost_stock_events.read() {
+        new_messages.push(format!(
+            "{} fell, {} left",
+            name(lost.player),
+            lost.stocks_left
+        ));
+    }
+    for eliminated in eliminated_events.read() {
+        new_messages.push(format!("{} is out!", name(eliminated.player)));
+    }
+    for round_over in round_over_events.read() {
+        new_messages.push(match round_over.winner {
+            Some(winner) => format!("{} wins the round!", name(winner)),
+            None => "Nobody wins the round".to_string(),
+        });
+    }
+
+    for (_, timer) in messages.0.iter_mut() {
+        timer.tick(time.delta());
+    }
+    messages.0.retain(|(_, timer)| !timer.finished());
+    for message in new_messages {
+        let timer = Timer::new(Duration::from_secs(3), TimerMode::Once);
+        messages.0.push((message, timer));
     }
 }
+
+// NOTE: damage and stocks of every player along the bottom of the screen, the practice box comes last
+fn player_hud_ui(
+    mut contexts: EguiContexts,
+    query: Query<(&DamagePercent, Option<&Stocks>, Option<&PlayerSlot>)>,
+    messages: Res<HudMessages>,
+) {
+    let mut players: Vec<_> = query.iter().collect();
+    players.sort_by_key(|(_, _, slot)| slot.map_or(usize::MAX, |slot| slot.0));
+    egui::Area::new(egui::Id::new("player_hud"))
+        .anchor(egui::Align2::CENTER_BOTTOM, [0.0, -16.0])
+        .show(contexts.ctx_mut(), |ui| {
+            for (message, _) in messages.0.iter() {
+                ui.label(egui::RichText::new(message).size(20.0).strong());
+            }
+            ui.horizontal(|ui| {
+                for (damage, stocks, slot) in players {
+                    // white without damage, fully red from 150 % on
+                    let fade = (255.0 * (1.0 - damage.0 / 150.0).clamp(0.0, 1.0)) as u8;
+                    ui.vertical(|ui| {
+                        ui.label(player_name(slot));
+                        ui.label(
+                            egui::RichText::new(format!("{:.0} %", damage.0))
+                                .size(28.0)
+                                .strong()
+                                .color(egui::Color32::from_rgb(255, fade, fade)),
+                        );
+                        if let Some(stocks) = stocks {
+                            ui.label("●".repeat(stocks.0 as usize));
+                        }
+                    });
+                    ui.add_space(24.0);
+                }
+            });
+        });
+}
@@ -0,0 +1,139 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{
+    bot::{BotDifficulty, PracticeBox, PracticeSettings},
+    input::InputRecorder,
+    PlayerSlot,
+};
+
+pub struct PracticeMenuPlugin;
+
+#[derive(Resource, Default)]
+pub struct PracticeMenu {
+    pub open: bool,
+}
+
+impl Plugin for PracticeMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PracticeMenu>()
+            .add_systems(Update, (toggle_practice_menu, practice_menu_ui).chain());
+    }
+}
+
+fn toggle_practice_menu(keys: Res<ButtonInput<KeyCode>>, mut menu: ResMut<PracticeMenu>) {
+    if keys.just_pressed(KeyCode::F4) {
+        menu.open = !menu.open;
+    }
+}
+
+// NOTE: the first local player records the input the dummy replays
+fn practice_menu_ui(
+    mut cmd: Commands,
+    mut contexts: EguiContexts,
+    mut menu: ResMut<PracticeMenu>,
+    mut settings: ResMut<PracticeSettings>,
+    recorders: Query<(Entity, &PlayerSlot, Option<&InputRecorder>)>,
+) {
+    if !menu.open {
+        return;
+    }
+    let recorder = recorders.iter().find(|(_, slot, _)| slot.0 == 0);
+    let recording = recorder.is_some_and(|(_, _, recorder)| recorder.is_some());
+
+    // NOTE: only write back on a change, the practice boxes are updated when the settings change
+    let mut opponent = settings.opponent;
+    let seconds = |duration: Duration| duration.as_secs_f32();
+    let mut attack_interval = seconds(settings.attack_interval);
+    let mut attack_charge = seconds(settings.attack_charge);
+    let mut jump_interval = seconds(settings.jump_interval);
+    let mut toggle_recording = false;
+    egui::Window::new("Practice")
+        .open(&mut menu.open)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Dummy");
+                for dummy in PracticeBox::DUMMIES {
+                    ui.selectable_value(&mut opponent, dummy, dummy.name());
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Bot");
+                for difficulty in BotDifficulty::ALL {
+                    ui.selectable_value(
+                        &mut opponent,
+                        PracticeBox::Bot(difficulty),
+                        difficulty.name(),
+                    );
+                }
+            });
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label("Attack every");
+                ui.add(
+                    egui::DragValue::new(&mut attack_interval)
+                        .suffix(" s")
+                        .speed(0.1)
+                        .clamp_range(0.5..=10.0),
+                );
+                ui.label("charged for");
+                ui.add(
+                    egui::DragValue::new(&mut attack_charge)
+                        .suffix(" s")
+                        .speed(0.1)
+                        .clamp_range(0.1..=(attack_interval - 0.1).min(3.0)),
+                );
+            });
+            ui.horizontal(|ui| {
+                ui.label("Jump every");
+                ui.add(
+                    egui::DragValue::new(&mut jump_interval)
+                        .suffix(" s")
+                        .speed(0.1)
+                        .clamp_range(0.5..=10.0),
+                );
+            });
+            ui.horizontal(|ui| {
+                let label = if recording {
+                    "Stop recording"
+                } else {
+                    "Record"
+                };
+                toggle_recording = ui
+                    .add_enabled(recorder.is_some(), egui::Button::new(label))
+                    .clicked();
+                ui.label(format!(
+                    "Recorded {:.1} s of player 1",
+                    settings.recording.duration()
+                ));
+            });
+        });
+
+    if toggle_recording {
+        if let Some((entity, _, current)) = recorder {
+            match current {
+                Some(current) => {
+                    settings.recording = current.recording.clone();
+                    cmd.entity(entity).remove::<InputRecorder>();
+                }
+                None => {
+                    cmd.entity(entity).insert(InputRecorder::default());
+                }
+            }
+        }
+    }
+    // NOTE: a charge as long as the interval never releases the attack
+    attack_charge = attack_charge.min(attack_interval - 0.1);
+    let changed = opponent != settings.opponent
+        || attack_interval != seconds(settings.attack_interval)
+        || attack_charge != seconds(settings.attack_charge)
+        || jump_interval != seconds(settings.jump_interval);
+    if changed {
+        settings.opponent = opponent;
+        settings.attack_interval = Duration::from_secs_f32(attack_interval);
+        settings.attack_charge = Duration::from_secs_f32(attack_charge);
+        settings.jump_interval = Duration::from_secs_f32(jump_interval);
+    }
+}
@@ -0,0 +1,149 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{
+    key_bindings::{Action, InputDevice, KeyBindings, KeyBindingsConfig, MAX_LOCAL_PLAYERS},
+    PlayerSlot,
+};
+
+pub struct RebindMenuPlugin;
+
+#[derive(Resource, Default)]
+pub struct RebindMenu {
+    pub open: bool,
+    slot: usize,
+    capturing: Option<Action>,
+    message: Option<String>,
+}
+
+impl Plugin for RebindMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RebindMenu>().add_systems(
+            Update,
+            (toggle_rebind_menu, capture_key_binding, rebind_menu_ui).chain(),
+        );
+    }
+}
+
+fn toggle_rebind_menu(keys: Res<ButtonInput<KeyCode>>, mut menu: ResMut<RebindMenu>) {
+    if menu.capturing.is_some() || !keys.just_pressed(KeyCode::F1) {
+        return;
+    }
+    menu.open = !menu.open;
+    menu.message = None;
+}
+
+// NOTE: binds the next pressed key to the action selected in the menu, escape cancels
+fn capture_key_binding(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut menu: ResMut<RebindMenu>,
+    mut config: ResMut<KeyBindingsConfig>,
+    query: Query<(&PlayerSlot, &mut InputDevice)>,
+) {
+    let Some(action) = menu.capturing else {
+        return;
+    };
+    let Some(&key) = keys.get_just_pressed().next() else {
+        return;
+    };
+    menu.capturing = None;
+    if key == KeyCode::Escape {
+        menu.message = None;
+        return;
+    }
+
+    let slot = menu.slot;
+    let conflicts = config.conflicts(slot, action, key);
+    menu.message = conflicts.first().map(|(other_slot, other_action)| {
+        format!(
+            "{:?} is also bound to {:?} of player {}!",
+            key,
+            other_action,
+            other_slot + 1
+        )
+    });
+    let keys = config.slot_mut(slot).keys_mut(action);
+    if !keys.contains(&key) {
+        keys.push(key);
+    }
+    apply_key_bindings(&config, query);
+}
+
+fn rebind_menu_ui(
+    mut contexts: EguiContexts,
+    mut menu: ResMut<RebindMenu>,
+    mut config: ResMut<KeyBindingsConfig>,
+    query: Query<(&PlayerSlot, &mut InputDevice)>,
+) {
+    if !menu.open {
+        return;
+    }
+    let mut open = menu.open;
+    let mut changed = false;
+    egui::Window::new("Key Bindings")
+        .open(&mut open)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                for slot in 0..MAX_LOCAL_PLAYERS {
+                    ui.selectable_value(&mut menu.slot, slot, format!("Player {}", slot + 1));
+                }
+            });
+            ui.separator();
+
+            let slot = menu.slot;
+            egui::Grid::new("key_bindings").show(ui, |ui| {
+                for action in Action::ALL {
+                    ui.label(format!("{:?}", action));
+                    let mut removed = None;
+                    for &key in config.slot(slot).keys(action) {
+                        let mut text = egui::RichText::new(format!("{:?}", key));
+                        if !config.conflicts(slot, action, key).is_empty() {
+                            text = text.color(egui::Color32::RED);
+                        }
+                        if ui.button(text).on_hover_text("Click to remove").clicked() {
+                            removed = Some(key);
+                        }
+                    }
+                    if let Some(key) = removed {
+                        config.slot_mut(slot).keys_mut(action).retain(|&k| k != key);
+                        changed = true;
+                    }
+                    if menu.capturing == Some(action) {
+                        ui.label("Press a key...");
+                    } else if ui.button("+").clicked() {
+                        menu.capturing = Some(action);
+                        menu.message = None;
+                    }
+                    ui.end_row();
+                }
+            });
+            ui.separator();
+
+            if let Some(message) = &menu.message {
+                ui.colored_label(egui::Color32::RED, message);
+            }
+            if ui.button("Reset to defaults").clicked() {
+                *config.slot_mut(slot) = KeyBindings::for_slot(slot);
+                changed = true;
+            }
+        });
+    menu.open = open;
+    if !open {
+        menu.capturing = None;
+    }
+    if changed {
+        apply_key_bindings(&config, query);
+    }
+}
+
+fn apply_key_bindings(
+    config: &KeyBindingsConfig,
+    mut query: Query<(&PlayerSlot, &mut InputDevice)>,
+) {
+    config.save();
+    for (slot, mut device) in query.iter_mut() {
+        if let InputDevice::Keyboard(bindings) = device.as_mut() {
+            *bindings = config.slot(slot.0);
+        }
+    }
+}
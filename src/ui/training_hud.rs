@@ -0,0 +1,99 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{
+    bot::{PracticeBox, PracticeSettings},
+    Player, PlayerActionState, PlayerActionValues, PlayerHit, PlayerSlot,
+};
+
+pub struct TrainingHudPlugin;
+
+// NOTE: hits since the game started, the strongest one is kept to compare combos
+#[derive(Resource, Default)]
+pub struct TrainingStats {
+    pub last_hit: Option<PlayerHit>,
+    pub strongest_hit: Option<PlayerHit>,
+}
+
+impl Plugin for TrainingHudPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TrainingStats>()
+            .add_systems(Update, (record_hits, training_hud_ui).chain());
+    }
+}
+
+fn record_hits(mut hit_events: EventReader<PlayerHit>, mut stats: ResMut<TrainingStats>) {
+    for hit in hit_events.read() {
+        stats.last_hit = Some(*hit);
+        let strongest = stats
+            .strongest_hit
+            .map_or(0.0, |strongest| strongest.impulse.length());
+        if hit.impulse.length() > strongest {
+            stats.strongest_hit = Some(*hit);
+        }
+    }
+}
+
+// NOTE: only shown while a training dummy is the opponent
+fn training_hud_ui(
+    mut contexts: EguiContexts,
+    query: Query<(Entity, &PlayerActionState, Option<&PlayerSlot>), With<Player>>,
+    stats: Res<TrainingStats>,
+    settings: Res<PracticeSettings>,
+    values: Res<PlayerActionValues>,
+) {
+    if !settings.opponent.is_dummy() {
+        return;
+    }
+    let name = |entity: Entity| match query.get(entity) {
+        Ok((_, _, Some(slot))) => format!("Player {}", slot.0 + 1),
+        Ok((_, _, None)) => "Dummy".to_string(),
+        Err(_) => "Gone".to_string(),
+    };
+    let describe = |hit: &PlayerHit| {
+        format!(
+            "{} hit {}: impulse {:.1} at strength {:.0}%",
+            name(hit.attacker),
+            name(hit.target),
+            hit.impulse.length(),
+            hit.strength * 100.0
+        )
+    };
+
+    egui::Window::new("Training")
+        .anchor(egui::Align2::RIGHT_TOP, [-8.0, 8.0])
+        .show(contexts.ctx_mut(), |ui| {
+            let mut players: Vec<_> = query.iter().collect();
+            players.sort_by_key(|(_, _, slot)| slot.map_or(usize::MAX, |slot| slot.0));
+            for (entity, state, _) in players {
+                // the strength the attack would have if it was released now
+                let strength = match state {
+                    PlayerActionState::PrepareAttack(dur) => values.attack_strength(*dur),
+                    PlayerActionState::Attack(_, strength) => *strength,
+                    _ => 0.0,
+                };
+                ui.horizontal(|ui| {
+                    ui.label(name(entity));
+                    ui.add(
+                        egui::ProgressBar::new(strength)
+                            .desired_width(120.0)
+                            .text(format!("{:.0}%", strength * 100.0)),
+                    );
+                });
+            }
+            ui.separator();
+            match &stats.last_hit {
+                Some(hit) => ui.label(format!("Last: {}", describe(hit))),
+                None => ui.label("No hits yet"),
+            };
+            if let Some(hit) = &stats.strongest_hit {
+                ui.label(format!("Strongest: {}", describe(hit)));
+            }
+            if settings.opponent == PracticeBox::Replay {
+                ui.label(format!(
+                    "Replaying {:.1} s of recorded input",
+                    settings.recording.duration()
+                ));
+            }
+        });
+}